@@ -0,0 +1,115 @@
+use crate::{
+    duration::format_duration,
+    stats::{get_median, Stats},
+};
+use std::time::Duration;
+
+/// The spread of a single metric across the summaries of several runs.
+pub struct Spread {
+    pub name: &'static str,
+    /// The median of the values of the runs.
+    pub median: Duration,
+    pub min: Duration,
+    /// The worst value of the runs.
+    pub max: Duration,
+    pub stddev: Duration,
+}
+
+impl Spread {
+    /// Computes the spread of the given values. Returns `None` if
+    /// `values` is empty.
+    fn from_values(name: &'static str, mut values: Vec<Duration>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        values.sort();
+        let n = values.len() as f64;
+        let avg = values.iter().map(|v| v.as_nanos() as f64).sum::<f64>() / n;
+        let var = values
+            .iter()
+            .map(|v| (v.as_nanos() as f64 - avg).powi(2))
+            .sum::<f64>()
+            / n;
+
+        Some(Self {
+            name,
+            median: get_median(&values),
+            min: values[0],
+            max: values[values.len() - 1],
+            stddev: Duration::from_nanos(var.sqrt() as u64),
+        })
+    }
+
+    /// Returns the standard deviation relative to the median in percent,
+    /// which makes the variability of metrics of different magnitudes
+    /// comparable.
+    pub fn variation(&self) -> f64 {
+        let median = self.median.as_nanos() as f64;
+        if median == 0f64 {
+            return 0f64;
+        }
+
+        self.stddev.as_nanos() as f64 / median * 100f64
+    }
+}
+
+/// Aggregates the latency metrics of the summaries of several runs.
+/// Returns an empty list if `stats` is empty.
+pub fn aggregate(stats: &[Stats]) -> Vec<Spread> {
+    let spread = |name, get: fn(&Stats) -> Duration| {
+        Spread::from_values(name, stats.iter().map(get).collect())
+    };
+
+    [
+        spread("Average", |s| s.avg),
+        spread("Median", |s| s.median),
+        spread("90th %ile.", |s| s.p90),
+        spread("95th %ile.", |s| s.p95),
+        spread("99th %ile.", |s| s.p99),
+        spread("Max", |s| s.max),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+pub fn print_spreads(spreads: &[Spread], runs: usize) {
+    println!("Aggregate of {runs} runs:\n");
+    println!(
+        "{:<12}{:>14}{:>14}{:>14}{:>14}{:>10}",
+        "Metric", "Median", "Min", "Max", "Std. Dev.", "CV"
+    );
+
+    for s in spreads {
+        println!(
+            "{:<12}{:>14}{:>14}{:>14}{:>14}{:>9.2}%",
+            format!("{}:", s.name),
+            format!("{:.4}", format_duration(s.median)),
+            format!("{:.4}", format_duration(s.min)),
+            format!("{:.4}", format_duration(s.max)),
+            format!("{:.4}", format_duration(s.stddev)),
+            s.variation(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn spread() {
+        let values = [30, 10, 20, 40].map(Duration::from_millis).to_vec();
+        let s = Spread::from_values("", values).unwrap();
+        assert_eq!(Duration::from_millis(25), s.median);
+        assert_eq!(Duration::from_millis(10), s.min);
+        assert_eq!(Duration::from_millis(40), s.max);
+        // The population standard deviation of 10, 20, 30 and 40 is
+        // sqrt(125) ms.
+        assert_eq!(11180339, s.stddev.as_nanos());
+        assert!((s.variation() - 44.7213).abs() < 1e-3);
+
+        assert!(Spread::from_values("", vec![]).is_none());
+    }
+}
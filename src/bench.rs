@@ -0,0 +1,833 @@
+use crate::{
+    duration::DurationRange,
+    rate::{self, Controller, RateReport, TargetRps},
+    request::{Client, ClientConfig, Failure, FailureKind, Response},
+    scenario::Target,
+    stats::Stats,
+    tls::Handshakes,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::{
+    collections::VecDeque,
+    future::Future,
+    mem,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::{
+    runtime::{self, Runtime},
+    sync::Semaphore,
+};
+
+/// The configuration of a benchmark run via [`run_benchmark`].
+#[derive(Debug)]
+pub struct BenchmarkConfig {
+    pub client: ClientConfig,
+    pub load: Load,
+    /// The maximum amount of requests sent concurrently.
+    pub parallel: usize,
+    /// The stack size of the worker threads in bytes; the default of
+    /// the platform is used if unset.
+    pub stack_size: Option<usize>,
+    /// The requests sent before the measured run.
+    pub warmup: Option<Warmup>,
+    /// The duration awaited before each request is sent.
+    pub wait: Option<DurationRange>,
+}
+
+/// Runs a benchmark with the given configuration and returns the
+/// statistics of the measured requests. Fails if no request could be
+/// completed.
+pub fn run_benchmark(config: BenchmarkConfig) -> Result<Stats> {
+    let run = Run {
+        target: Target {
+            url: config.client.urls.first().cloned().unwrap_or_default(),
+            ..Default::default()
+        },
+        client: Arc::new(Client::new(config.client)?),
+        load: config.load,
+        parallel: config.parallel,
+        stack_size: config.stack_size,
+        error_window: None,
+        target_rps: None,
+        burst: false,
+    };
+
+    let result = run.perform(config.warmup, None, config.wait.as_ref(), None)?;
+    Stats::from_responses(&result.responses, &result.failures).ok_or_else(|| {
+        match result.failures.first() {
+            Some(f) => anyhow::anyhow!("no request could be completed: {}", f.message),
+            None => anyhow::anyhow!("no request has been sent"),
+        }
+    })
+}
+
+/// The smallest accepted stack size of the worker threads in bytes.
+pub const MIN_STACK_SIZE: usize = 64 * 1024;
+
+/// Builds the runtime sending the requests. Since the requests are sent
+/// asynchronously, the amount of worker threads is limited to the
+/// available parallelism instead of the amount of concurrent requests.
+pub fn build_runtime(parallel: usize, stack_size: Option<usize>) -> Result<Runtime> {
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let mut builder = runtime::Builder::new_multi_thread();
+    builder
+        .worker_threads(threads.min(parallel).max(1))
+        .enable_all();
+    if let Some(size) = stack_size {
+        builder.thread_stack_size(size);
+    }
+    Ok(builder.build()?)
+}
+
+/// A callback invoked with the result of each measured request as soon
+/// as it completes.
+pub type Observer = Arc<dyn Fn(&Result<Response, Failure>) + Send + Sync>;
+
+/// A benchmark run against a single target.
+pub struct Run {
+    pub target: Target,
+    pub client: Arc<Client>,
+    pub load: Load,
+    pub parallel: usize,
+    pub stack_size: Option<usize>,
+    /// Aborts the run if the error rate within the window is exceeded.
+    pub error_window: Option<Arc<ErrorWindow>>,
+    /// Adjusts the concurrency to sustain a target rate instead of
+    /// keeping `parallel` requests in flight.
+    pub target_rps: Option<TargetRps>,
+    /// Sends the requests in bursts of `parallel` requests instead of
+    /// keeping `parallel` requests in flight.
+    pub burst: bool,
+}
+
+/// The amount of requests in flight at the same time during a run.
+#[derive(Debug, Clone, Copy)]
+pub enum Concurrency {
+    Fixed(usize),
+    /// The concurrency is adjusted once per interval to sustain the
+    /// target rate; requests are paced to not exceed it.
+    Target(TargetRps),
+    /// The requests are sent in bursts of the given amount, each of which
+    /// is sent at once after the previous one has completed.
+    Burst(usize),
+}
+
+/// The amount of requests sent by a run.
+#[derive(Debug, Clone)]
+pub enum Load {
+    Count(u32),
+    /// Requests are sent until `duration` has elapsed; requests still in
+    /// flight after the `drain` period are aborted and discarded.
+    Duration {
+        duration: Duration,
+        drain: Option<Duration>,
+    },
+    /// Requests are sent until the bodies of the responses add up to
+    /// the given amount of bytes; requests in flight at that point are
+    /// still completed. Requires the bodies to be read.
+    Bytes(u64),
+    /// One request is sent at each of the given offsets from the start
+    /// of the run, e.g. to reproduce the arrival pattern of a prior run.
+    Replay(Arc<[Duration]>),
+}
+
+/// The requests sent before the measured run.
+#[derive(Debug, Clone, Copy)]
+pub enum Warmup {
+    Count(u32),
+    /// Warmup requests are sent in batches of `window` requests until
+    /// the coefficient of variation of the latencies of the last batch
+    /// is at most `max_cov` or `max` requests have been sent.
+    UntilStable {
+        window: u32,
+        max_cov: f64,
+        max: u32,
+    },
+}
+
+/// The outcome of a [`Warmup::UntilStable`] warmup.
+#[derive(Debug, Clone, Copy)]
+pub struct Stabilization {
+    /// The amount of sent warmup requests.
+    pub requests: u32,
+    /// The coefficient of variation of the last batch.
+    pub cov: f64,
+    pub stable: bool,
+}
+
+pub struct RunResult {
+    pub responses: Vec<Response>,
+    pub failures: Vec<Failure>,
+    pub elapsed: Duration,
+    /// The amount of connections established during the run, if they
+    /// could be detected.
+    pub connections: Option<usize>,
+    /// The TLS handshakes performed during the run, if they were counted.
+    pub handshakes: Option<Handshakes>,
+    /// The amount of requests which were aborted after the drain period
+    /// and are not included in the results.
+    pub discarded: usize,
+    pub stabilization: Option<Stabilization>,
+    /// The state of the error window if the run has been aborted.
+    pub aborted: Option<WindowState>,
+    /// The concurrency over time, if the run had a target rate.
+    pub rate: Option<RateReport>,
+}
+
+/// A sliding window over the outcomes of the most recent requests, which
+/// aborts a run as soon as the share of failed requests within the full
+/// window exceeds the maximum error rate.
+pub struct ErrorWindow {
+    size: usize,
+    /// The maximum error rate in percent.
+    max_rate: f64,
+    outcomes: Mutex<VecDeque<bool>>,
+    completed: AtomicUsize,
+    tripped: OnceLock<WindowState>,
+}
+
+/// The outcomes within an [`ErrorWindow`] at the time it was exceeded.
+#[derive(Debug, Clone, Copy)]
+pub struct WindowState {
+    /// The amount of failed requests within the window.
+    pub failed: usize,
+    pub size: usize,
+    /// The amount of requests completed before the run was aborted.
+    pub completed: usize,
+}
+
+impl WindowState {
+    /// Returns the error rate within the window in percent.
+    pub fn rate(&self) -> f64 {
+        self.failed as f64 / self.size as f64 * 100f64
+    }
+}
+
+impl ErrorWindow {
+    pub fn new(size: usize, max_rate: f64) -> Self {
+        Self {
+            size,
+            max_rate,
+            outcomes: Mutex::new(VecDeque::with_capacity(size)),
+            completed: AtomicUsize::new(0),
+            tripped: OnceLock::new(),
+        }
+    }
+
+    /// Records the outcome of a completed request.
+    fn record(&self, res: &Result<Response, Failure>) {
+        let failed = !res.as_ref().is_ok_and(Response::is_success);
+        let completed = self.completed.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let mut outcomes = self.outcomes.lock().expect("error window lock poisoned");
+        if outcomes.len() == self.size {
+            outcomes.pop_front();
+        }
+        outcomes.push_back(failed);
+
+        if outcomes.len() == self.size {
+            let state = WindowState {
+                failed: outcomes.iter().filter(|&&f| f).count(),
+                size: self.size,
+                completed,
+            };
+            if state.rate() > self.max_rate {
+                let _ = self.tripped.set(state);
+            }
+        }
+    }
+
+    fn is_tripped(&self) -> bool {
+        self.tripped.get().is_some()
+    }
+}
+
+/// Returns whether the run has been aborted by the error window, if any.
+fn is_aborted(window: Option<&ErrorWindow>) -> bool {
+    window.is_some_and(ErrorWindow::is_tripped)
+}
+
+/// Passes the result of a completed request to the observer and the
+/// error window, if any.
+fn complete(
+    res: &Result<Response, Failure>,
+    observer: Option<&Observer>,
+    window: Option<&ErrorWindow>,
+) {
+    if let Some(observer) = observer {
+        observer(res);
+    }
+    if let Some(window) = window {
+        window.record(res);
+    }
+}
+
+impl Run {
+    pub fn perform(
+        &self,
+        warmup: Option<Warmup>,
+        start_at: Option<DateTime<Utc>>,
+        wait: Option<&DurationRange>,
+        observer: Option<Observer>,
+    ) -> Result<RunResult> {
+        if matches!(self.load, Load::Bytes(_)) && !self.client.reads_body() {
+            anyhow::bail!("counting the transferred bytes requires the response bodies to be read");
+        }
+
+        let (concurrency, threads) = match self.target_rps {
+            Some(target) => (Concurrency::Target(target), target.max_parallel),
+            None if self.burst => (Concurrency::Burst(self.parallel), self.parallel),
+            None => (Concurrency::Fixed(self.parallel), self.parallel),
+        };
+        let runtime = build_runtime(threads, self.stack_size)?;
+
+        let stabilization = match warmup {
+            Some(Warmup::Count(n)) if n > 0 => {
+                perform_requests(&runtime, &self.client, self.parallel, n, wait, None);
+                None
+            }
+            Some(Warmup::UntilStable {
+                window,
+                max_cov,
+                max,
+            }) => Some(warmup_until_stable(
+                &runtime,
+                &self.client,
+                self.parallel,
+                window,
+                max_cov,
+                max,
+                wait,
+            )),
+            _ => None,
+        };
+
+        if let Some(start_at) = start_at {
+            thread::sleep((start_at - Utc::now()).to_std().unwrap_or_default());
+        }
+
+        let before = self.client.connections();
+        let handshakes = self.client.handshakes();
+        let mut result = measure(
+            &runtime,
+            &self.client,
+            &self.load,
+            concurrency,
+            wait,
+            observer,
+            self.error_window.clone(),
+        );
+        result.connections = self.client.connections().zip(before).map(|(a, b)| a - b);
+        result.handshakes = self.client.handshakes().zip(handshakes).map(|(a, b)| a - b);
+        result.stabilization = stabilization;
+
+        Ok(result)
+    }
+}
+
+impl RunResult {
+    /// Returns the average amount of requests which have been in flight
+    /// at the same time over the duration of the run.
+    pub fn achieved_concurrency(&self) -> f64 {
+        let busy: Duration = self
+            .responses
+            .iter()
+            .map(|r| r.took)
+            .chain(self.failures.iter().map(|f| f.took))
+            .sum();
+        busy.as_secs_f64() / self.elapsed.as_secs_f64()
+    }
+
+    /// Returns the share of requests which have been sent over an already
+    /// established connection, if the connections could be detected.
+    pub fn connection_reuse(&self) -> Option<f64> {
+        let requests = self.responses.len() + self.failures.len();
+        let reused = requests.saturating_sub(self.connections?);
+        Some(reused as f64 / requests.max(1) as f64)
+    }
+
+    /// Returns the summed sizes of the response bodies, if they have been
+    /// read.
+    pub fn transferred(&self) -> Option<u64> {
+        self.responses
+            .iter()
+            .map(|r| r.body_size)
+            .reduce(|a, b| Some(a? + b?))?
+    }
+
+    /// Returns the summed sizes of the sent request bodies, if any have
+    /// been sent.
+    pub fn uploaded(&self) -> Option<u64> {
+        self.responses
+            .iter()
+            .filter_map(|r| r.sent_size)
+            .reduce(|a, b| a + b)
+    }
+
+    /// Returns the amount of requests per second.
+    pub fn throughput(&self) -> f64 {
+        (self.responses.len() + self.failures.len()) as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+pub fn measure(
+    runtime: &Runtime,
+    client: &Arc<Client>,
+    load: &Load,
+    concurrency: Concurrency,
+    wait: Option<&DurationRange>,
+    observer: Option<Observer>,
+    window: Option<Arc<ErrorWindow>>,
+) -> RunResult {
+    let (parallel, controller) = match concurrency {
+        Concurrency::Fixed(parallel) | Concurrency::Burst(parallel) => (parallel, None),
+        Concurrency::Target(target) => {
+            let controller = Controller::new(target);
+            (
+                controller.parallel(),
+                Some(Arc::new(Mutex::new(controller))),
+            )
+        }
+    };
+    let mut dispatcher = Dispatcher::new(client, parallel, wait, observer, window.clone());
+    match concurrency {
+        Concurrency::Target(target) => {
+            dispatcher.period = Some(Duration::from_secs_f64(1f64 / target.rps));
+        }
+        Concurrency::Burst(_) => dispatcher.burst = true,
+        Concurrency::Fixed(_) => {}
+    }
+
+    let started = Instant::now();
+    let results = runtime.block_on(async {
+        let control = controller
+            .clone()
+            .map(|c| tokio::spawn(dispatcher.control(c)));
+        match *load {
+            Load::Count(n) => perform_counted(&dispatcher, n).await,
+            Load::Duration { duration, drain } => {
+                perform_requests_for(&dispatcher, duration, drain).await
+            }
+            Load::Bytes(bytes) => perform_requests_until(&dispatcher, bytes).await,
+            Load::Replay(ref offsets) => perform_replayed(&dispatcher, offsets).await,
+        }
+        if let Some(control) = control {
+            control.abort();
+            let _ = control.await;
+        }
+        dispatcher.finish().await
+    });
+    let elapsed = started.elapsed();
+    let completed = results.responses.len() + results.failures.len();
+
+    RunResult {
+        responses: results.responses,
+        failures: results.failures,
+        elapsed,
+        connections: None,
+        handshakes: None,
+        discarded: results.discarded,
+        stabilization: None,
+        aborted: window.and_then(|w| w.tripped.get().copied()),
+        rate: controller.map(|c| {
+            let achieved = completed as f64 / elapsed.as_secs_f64();
+            c.lock().expect("controller lock poisoned").report(achieved)
+        }),
+    }
+}
+
+fn warmup_until_stable(
+    runtime: &Runtime,
+    client: &Arc<Client>,
+    parallel: usize,
+    window: u32,
+    max_cov: f64,
+    max: u32,
+    wait: Option<&DurationRange>,
+) -> Stabilization {
+    let mut requests = 0;
+    loop {
+        let n = window.min(max - requests);
+        let (responses, failures) = perform_requests(runtime, client, parallel, n, wait, None);
+        requests += n;
+
+        let times: Vec<_> = responses
+            .iter()
+            .map(|r| r.took)
+            .chain(failures.iter().map(|f| f.took))
+            .collect();
+        let cov = coefficient_of_variation(&times);
+        let stable = cov <= max_cov;
+
+        if stable || requests >= max {
+            return Stabilization {
+                requests,
+                cov,
+                stable,
+            };
+        }
+    }
+}
+
+/// Returns the standard deviation of the given latencies relative to
+/// their mean; returns infinity if there are no latencies or their mean
+/// is zero.
+fn coefficient_of_variation(times: &[Duration]) -> f64 {
+    let n = times.len() as f64;
+    let mean = times.iter().map(Duration::as_secs_f64).sum::<f64>() / n;
+    if times.is_empty() || mean == 0f64 {
+        return f64::INFINITY;
+    }
+
+    let var = times
+        .iter()
+        .map(|t| (t.as_secs_f64() - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    var.sqrt() / mean
+}
+
+/// Sends each request in a separate task of the runtime, bounding the
+/// amount of requests in flight at the same time by a semaphore with
+/// `parallel` permits.
+struct Dispatcher {
+    client: Arc<Client>,
+    wait: Option<DurationRange>,
+    observer: Option<Observer>,
+    window: Option<Arc<ErrorWindow>>,
+    /// The current amount of permits, which is adjusted by the
+    /// controller of a run with a target rate.
+    parallel: Arc<AtomicUsize>,
+    permits: Arc<Semaphore>,
+    results: Arc<Mutex<Results>>,
+    /// The minimum time between two dispatched requests, if any.
+    period: Option<Duration>,
+    /// The point in time at which the next paced request is dispatched.
+    next_slot: Mutex<Option<tokio::time::Instant>>,
+    /// Dispatches the requests in bursts of `parallel` requests.
+    burst: bool,
+}
+
+/// The results of the requests sent via a [`Dispatcher`].
+#[derive(Default)]
+struct Results {
+    responses: Vec<Response>,
+    failures: Vec<Failure>,
+    /// The amount of requests which have been aborted after the drain
+    /// period.
+    discarded: usize,
+    /// The summed sizes of the response bodies.
+    transferred: u64,
+    /// The summed latencies of the completed requests.
+    busy: Duration,
+}
+
+impl Dispatcher {
+    fn new(
+        client: &Arc<Client>,
+        parallel: usize,
+        wait: Option<&DurationRange>,
+        observer: Option<Observer>,
+        window: Option<Arc<ErrorWindow>>,
+    ) -> Self {
+        let parallel = parallel.clamp(1, u32::MAX as usize);
+        Self {
+            client: client.clone(),
+            wait: wait.cloned(),
+            observer,
+            window,
+            parallel: Arc::new(AtomicUsize::new(parallel)),
+            permits: Arc::new(Semaphore::new(parallel)),
+            results: Arc::default(),
+            period: None,
+            next_slot: Mutex::default(),
+            burst: false,
+        }
+    }
+
+    /// Returns a task which adjusts the amount of permits once per
+    /// interval to the concurrency returned by the controller. Lowering
+    /// it waits until enough requests in flight have completed.
+    fn control(
+        &self,
+        controller: Arc<Mutex<Controller>>,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let parallel = self.parallel.clone();
+        let permits = self.permits.clone();
+        let results = self.results.clone();
+
+        async move {
+            let started = tokio::time::Instant::now();
+            let mut interval = tokio::time::interval_at(started + rate::INTERVAL, rate::INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let (mut last, mut completed, mut busy) = (started, 0, Duration::ZERO);
+
+            loop {
+                let now = interval.tick().await;
+                let (curr_completed, curr_busy) = {
+                    let results = results.lock().expect("results lock poisoned");
+                    (
+                        results.responses.len() + results.failures.len(),
+                        results.busy,
+                    )
+                };
+                let next = controller.lock().expect("controller lock poisoned").adjust(
+                    now - started,
+                    now - last,
+                    curr_completed - completed,
+                    curr_busy - busy,
+                );
+                (last, completed, busy) = (now, curr_completed, curr_busy);
+
+                let curr = parallel.load(Ordering::Relaxed);
+                if next > curr {
+                    permits.add_permits(next - curr);
+                } else if next < curr {
+                    permits
+                        .acquire_many((curr - next) as u32)
+                        .await
+                        .expect("the semaphore is never closed")
+                        .forget();
+                }
+                parallel.store(next, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Waits until the next slot if the requests are paced. Slots missed
+    /// by less than an interval are caught up, so that short stalls do
+    /// not lower the rate.
+    async fn pace(&self) {
+        let Some(period) = self.period else {
+            return;
+        };
+
+        let now = tokio::time::Instant::now();
+        let at = {
+            let mut next = self.next_slot.lock().expect("slot lock poisoned");
+            let at = next.filter(|&at| at + rate::INTERVAL > now).unwrap_or(now);
+            *next = Some(at + period);
+            at
+        };
+        tokio::time::sleep_until(at).await;
+    }
+
+    /// Waits for the next slot if the requests are paced and until less
+    /// than `parallel` requests are in flight, or until the previous burst
+    /// has completed if the request starts a burst, then sends the request
+    /// with the dispatch index `i` in a new task, after the wait. The
+    /// request is skipped if `deadline` has passed after the
+    /// wait and discarded if it is aborted with a timeout at `stop`.
+    async fn dispatch(&self, i: usize, deadline: Option<Instant>, stop: Option<Instant>) {
+        self.pace().await;
+        let parallel = self.parallel.load(Ordering::Relaxed);
+        if self.burst && i.is_multiple_of(parallel) {
+            // Waits until the previous burst has completed, so that all
+            // requests of this burst are dispatched at once.
+            let _all = self
+                .permits
+                .acquire_many(parallel as u32)
+                .await
+                .expect("the semaphore is never closed");
+        }
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+        let client = self.client.clone();
+        let wait = self.wait.clone();
+        let observer = self.observer.clone();
+        let window = self.window.clone();
+        let results = self.results.clone();
+
+        tokio::spawn(async move {
+            let _permit = permit;
+            if let Some(wait) = &wait {
+                tokio::time::sleep(wait.get_random()).await;
+            }
+            if deadline.is_some_and(|d| Instant::now() >= d) || is_aborted(window.as_deref()) {
+                return;
+            }
+
+            let res = match stop {
+                Some(stop) => client.send_before(i, stop).await,
+                None => client.send(i).await,
+            };
+
+            if let (Err(f), Some(stop)) = (&res, stop) {
+                if f.kind == FailureKind::Timeout && Instant::now() >= stop {
+                    results.lock().expect("results lock poisoned").discarded += 1;
+                    return;
+                }
+            }
+
+            complete(&res, observer.as_ref(), window.as_deref());
+            let mut results = results.lock().expect("results lock poisoned");
+            results.busy += match &res {
+                Ok(r) => r.took,
+                Err(f) => f.took,
+            };
+            match res {
+                Ok(r) => {
+                    results.transferred += r.body_size.unwrap_or_default();
+                    results.responses.push(r);
+                }
+                Err(f) => results.failures.push(f),
+            }
+        });
+    }
+
+    fn is_aborted(&self) -> bool {
+        is_aborted(self.window.as_deref())
+    }
+
+    fn transferred(&self) -> u64 {
+        self.results
+            .lock()
+            .expect("results lock poisoned")
+            .transferred
+    }
+
+    /// Waits until all dispatched requests have completed and returns
+    /// their results.
+    async fn finish(&self) -> Results {
+        let _all = self
+            .permits
+            .acquire_many(self.parallel.load(Ordering::Relaxed) as u32)
+            .await
+            .expect("the semaphore is never closed");
+        mem::take(&mut *self.results.lock().expect("results lock poisoned"))
+    }
+}
+
+/// Sends `n` requests, skipping the remaining ones once the run has been
+/// aborted by the error window.
+async fn perform_counted(dispatcher: &Dispatcher, n: u32) {
+    for i in 0..n as usize {
+        if dispatcher.is_aborted() {
+            break;
+        }
+        dispatcher.dispatch(i, None, None).await;
+    }
+}
+
+/// Sends requests until `duration` has elapsed; requests in flight after
+/// the `drain` period are aborted and discarded.
+async fn perform_requests_for(
+    dispatcher: &Dispatcher,
+    duration: Duration,
+    drain: Option<Duration>,
+) {
+    let deadline = Instant::now() + duration;
+    let stop = drain.map(|d| deadline + d);
+
+    for i in 0.. {
+        if Instant::now() >= deadline || dispatcher.is_aborted() {
+            break;
+        }
+        dispatcher.dispatch(i, Some(deadline), stop).await;
+    }
+}
+
+/// The amount of requests after which a run limited by the transferred
+/// bytes is stopped if no bytes have been transferred at all, e.g.
+/// because all requests fail or all responses are empty.
+const MAX_REQUESTS_WITHOUT_BYTES: usize = 1000;
+
+/// Sends requests until the bodies of the responses add up to `bytes`.
+async fn perform_requests_until(dispatcher: &Dispatcher, bytes: u64) {
+    for i in 0.. {
+        let sent = dispatcher.transferred();
+        if sent >= bytes
+            || (sent == 0 && i >= MAX_REQUESTS_WITHOUT_BYTES)
+            || dispatcher.is_aborted()
+        {
+            break;
+        }
+        dispatcher.dispatch(i, None, None).await;
+    }
+}
+
+/// Sends one request at each of the offsets from now on. The requests
+/// are dispatched in order once less than `parallel` requests are in
+/// flight, so that they are sent late if too many are.
+async fn perform_replayed(dispatcher: &Dispatcher, offsets: &[Duration]) {
+    let started = tokio::time::Instant::now();
+    for (i, &offset) in offsets.iter().enumerate() {
+        tokio::time::sleep_until(started + offset).await;
+        if dispatcher.is_aborted() {
+            break;
+        }
+        dispatcher.dispatch(i, None, None).await;
+    }
+}
+
+/// Sends `n` requests, of which at most `parallel` are in flight at the
+/// same time.
+pub fn perform_requests(
+    runtime: &Runtime,
+    client: &Arc<Client>,
+    parallel: usize,
+    n: u32,
+    wait: Option<&DurationRange>,
+    observer: Option<Observer>,
+) -> (Vec<Response>, Vec<Failure>) {
+    let dispatcher = Dispatcher::new(client, parallel, wait, observer, None);
+    let results = runtime.block_on(async {
+        perform_counted(&dispatcher, n).await;
+        dispatcher.finish().await
+    });
+    (results.responses, results.failures)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cov() {
+        let times = [10, 10, 10].map(Duration::from_millis);
+        assert_eq!(0f64, coefficient_of_variation(&times));
+
+        let times = [5, 15].map(Duration::from_millis);
+        assert_eq!(0.5, coefficient_of_variation(&times));
+
+        assert!(coefficient_of_variation(&[]).is_infinite());
+    }
+
+    #[test]
+    fn error_window() {
+        let failure = || {
+            Err(Failure {
+                index: 0,
+                method: reqwest::Method::GET,
+                kind: FailureKind::Other,
+                message: String::new(),
+                reason: String::new(),
+                took: Duration::ZERO,
+                timestamp: Utc::now(),
+            })
+        };
+
+        let window = ErrorWindow::new(4, 50f64);
+        for _ in 0..3 {
+            window.record(&failure());
+        }
+        // The window is not evaluated before it is full.
+        assert!(!window.is_tripped());
+
+        window.record(&failure());
+        let state = window.tripped.get().unwrap();
+        assert_eq!((4, 4, 4), (state.failed, state.size, state.completed));
+        assert_eq!(100f64, state.rate());
+    }
+}
@@ -0,0 +1,207 @@
+use crate::size::SizeRange;
+use anyhow::Result;
+use encoding_rs::Encoding;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+/// The placeholder which is replaced with the dispatch index of the
+/// request in templated bodies.
+const INDEX_PLACEHOLDER: &str = "{{n}}";
+
+/// The minimum size of random bodies, from which on they are not
+/// expected to equal each other by chance.
+const MIN_VARYING_SIZE: u64 = 8;
+
+/// A request body which is either sent as is or rendered per request.
+#[derive(Debug, Clone)]
+pub enum Body {
+    Raw(Vec<u8>),
+    Template(Template),
+    Generated(std::sync::Arc<crate::generator::BodyGenerator>),
+    Sized(SizedBody),
+    #[cfg(feature = "body-template")]
+    Handlebars(std::sync::Arc<crate::template::BodyTemplate>),
+}
+
+impl Body {
+    /// Creates a templated body if `templated` is true and the body is
+    /// valid UTF-8; otherwise, the body is kept as is, so that binary
+    /// contents are never altered.
+    pub fn new(body: Vec<u8>, templated: bool) -> Self {
+        if !templated {
+            return Self::Raw(body);
+        }
+
+        match String::from_utf8(body) {
+            Ok(s) => Self::Template(Template::parse(&s)),
+            Err(err) => Self::Raw(err.into_bytes()),
+        }
+    }
+
+    pub fn is_template(&self) -> bool {
+        !matches!(self, Self::Raw(_))
+    }
+
+    /// Whether the body is expected to differ between requests, i.e. it
+    /// contains the dispatch index, a UUID or enough random bytes.
+    pub fn varies(&self) -> bool {
+        match self {
+            Self::Raw(_) => false,
+            Self::Template(t) => t.parts.contains(&Part::Index),
+            Self::Generated(g) => g.varies(),
+            Self::Sized(b) => !b.zeroed && b.size.min >= MIN_VARYING_SIZE,
+            #[cfg(feature = "body-template")]
+            Self::Handlebars(_) => true,
+        }
+    }
+
+    /// Returns the contents of the body for the request with the
+    /// dispatch index `i`.
+    pub fn render(&self, i: usize) -> Vec<u8> {
+        match self {
+            Self::Raw(b) => b.clone(),
+            Self::Template(t) => t.render(i).into_bytes(),
+            Self::Generated(g) => g.render(i).into_bytes(),
+            Self::Sized(b) => b.render(i),
+            #[cfg(feature = "body-template")]
+            Self::Handlebars(t) => t.render(i).into_bytes(),
+        }
+    }
+}
+
+/// A body of a random size, which is filled with either zero bytes or
+/// random bytes. The size and the contents only depend on the seed and
+/// the dispatch index, so that runs with the same seed send the same
+/// bodies.
+#[derive(Debug, Clone)]
+pub struct SizedBody {
+    pub size: SizeRange,
+    pub zeroed: bool,
+    pub seed: u64,
+}
+
+impl SizedBody {
+    fn render(&self, i: usize) -> Vec<u8> {
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(i as u64));
+        let mut body = vec![0; self.size.get_random(&mut rng) as usize];
+        if !self.zeroed {
+            rng.fill_bytes(&mut body);
+        }
+        body
+    }
+}
+
+/// Encodes the text in the charset with the given label, e.g.
+/// 'Shift_JIS'. Returns the encoded bytes and the canonical name of the
+/// charset, which is declared in the Content-Type header.
+pub fn encode(text: &str, label: &str) -> Result<(Vec<u8>, &'static str)> {
+    let encoding = Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| anyhow::anyhow!("unknown charset: '{label}'"))?;
+    let (bytes, encoding, had_errors) = encoding.encode(text);
+    if had_errors {
+        anyhow::bail!(
+            "the body contains characters which cannot be encoded in {}",
+            encoding.name()
+        );
+    }
+    Ok((bytes.into_owned(), encoding.name()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Index,
+}
+
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    fn parse(s: &str) -> Self {
+        let mut parts = vec![];
+
+        let mut rest = s;
+        while let Some((literal, tail)) = rest.split_once(INDEX_PLACEHOLDER) {
+            if !literal.is_empty() {
+                parts.push(Part::Literal(literal.to_string()));
+            }
+            parts.push(Part::Index);
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        Self { parts }
+    }
+
+    fn render(&self, i: usize) -> String {
+        self.parts
+            .iter()
+            .map(|p| match p {
+                Part::Literal(s) => s.clone(),
+                Part::Index => i.to_string(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn template() {
+        let body = Body::new(br#"{"id":{{n}},"n":"{{n}}{{n}}"}"#.to_vec(), true);
+        assert!(body.is_template());
+        assert!(body.varies());
+        assert_eq!(br#"{"id":7,"n":"77"}"#.to_vec(), body.render(7));
+        assert!(!Body::new(b"{}".to_vec(), true).varies());
+
+        let body = Body::new(b"{{n}}".to_vec(), false);
+        assert_eq!(b"{{n}}".to_vec(), body.render(7));
+    }
+
+    #[test]
+    fn template_binary() {
+        let raw = b"\xff\xfe{{n}}\x00\xc3\x28".to_vec();
+        let body = Body::new(raw.clone(), true);
+        assert!(!body.is_template());
+        assert_eq!(raw, body.render(7));
+    }
+
+    #[test]
+    fn encode_charset() {
+        let (bytes, name) = encode("日本", "shift_jis").unwrap();
+        assert_eq!((b"\x93\xfa\x96\x7b".to_vec(), "Shift_JIS"), (bytes, name));
+        let (bytes, name) = encode("é", "latin1").unwrap();
+        assert_eq!((vec![0xe9], "windows-1252"), (bytes, name));
+
+        assert!(encode("日本", "latin1").is_err());
+        assert!(encode("a", "klingon").is_err());
+    }
+
+    #[test]
+    fn sized() {
+        let sized = |zeroed| {
+            Body::Sized(SizedBody {
+                size: SizeRange { min: 10, max: 20 },
+                zeroed,
+                seed: 42,
+            })
+        };
+
+        let body = sized(false);
+        let rendered = body.render(7);
+        assert!((10..=20).contains(&rendered.len()));
+        assert_eq!(rendered, body.render(7));
+        assert!(rendered.iter().any(|&b| b != 0));
+        assert!(body.varies());
+
+        let zeroed = sized(true).render(7);
+        assert!(!sized(true).varies());
+        assert_eq!(rendered.len(), zeroed.len());
+        assert!(zeroed.iter().all(|&b| b == 0));
+    }
+}
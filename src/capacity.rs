@@ -0,0 +1,126 @@
+use crate::duration::format_duration;
+use std::time::Duration;
+
+/// The factor by which the 95th percentile latency of a probe may
+/// exceed the one of the first probe before the latency is considered
+/// degraded.
+const LATENCY_DEGRADATION_FACTOR: f64 = 2.0;
+
+/// The minimum relative throughput gain of a probe compared to the best
+/// previous probe; any smaller gain is considered a plateau.
+const MIN_THROUGHPUT_GAIN: f64 = 0.05;
+
+/// The result of a single probe run at a fixed concurrency.
+pub struct Probe {
+    pub parallel: usize,
+    pub throughput: f64,
+    pub p95: Duration,
+}
+
+/// Searches the concurrency which maximizes the throughput before the
+/// latency degrades by feeding it probes at increasing concurrency.
+#[derive(Default)]
+pub struct Search {
+    probes: Vec<Probe>,
+    best: usize,
+    stop_reason: Option<&'static str>,
+}
+
+impl Search {
+    /// Returns the concurrency values to be probed up to `max_parallel`,
+    /// doubling on each step.
+    pub fn steps(max_parallel: usize) -> impl Iterator<Item = usize> {
+        let mut next = Some(1usize);
+        std::iter::from_fn(move || {
+            let curr = next?.min(max_parallel);
+            next = (curr < max_parallel).then(|| curr * 2);
+            Some(curr)
+        })
+    }
+
+    /// Adds the result of a probe and returns whether the search should
+    /// continue with the next step.
+    pub fn push(&mut self, probe: Probe) -> bool {
+        if let Some(first) = self.probes.first() {
+            let best = &self.probes[self.best];
+
+            if probe.p95.as_secs_f64() > first.p95.as_secs_f64() * LATENCY_DEGRADATION_FACTOR {
+                self.stop_reason = Some("latency degraded");
+            } else if probe.throughput < best.throughput * (1f64 + MIN_THROUGHPUT_GAIN) {
+                self.stop_reason = Some("throughput plateaued");
+            } else {
+                self.best = self.probes.len();
+            }
+        }
+
+        self.probes.push(probe);
+        self.stop_reason.is_none()
+    }
+
+    /// Returns the probe with the recommended concurrency, if any.
+    pub fn recommended(&self) -> Option<&Probe> {
+        self.probes.get(self.best)
+    }
+
+    pub fn print(&self) {
+        println!("{:<12}{:>14}{:>14}", "Parallel", "Req/s", "95th %ile.");
+        for p in &self.probes {
+            println!(
+                "{:<12}{:>14.2}{:>14}",
+                p.parallel,
+                p.throughput,
+                format!("{:.4}", format_duration(p.p95)),
+            );
+        }
+
+        println!();
+        println!(
+            "Stopped:    {}",
+            self.stop_reason.unwrap_or("maximum parallel reached")
+        );
+        if let Some(p) = self.recommended() {
+            println!(
+                "Recommended parallel: {} ({:.2} req/s)",
+                p.parallel, p.throughput
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn probe(parallel: usize, throughput: f64, p95_ms: u64) -> Probe {
+        Probe {
+            parallel,
+            throughput,
+            p95: Duration::from_millis(p95_ms),
+        }
+    }
+
+    #[test]
+    fn steps() {
+        assert_eq!(vec![1, 2, 4, 8], Search::steps(8).collect::<Vec<_>>());
+        assert_eq!(vec![1, 2, 4, 5], Search::steps(5).collect::<Vec<_>>());
+        assert_eq!(vec![1], Search::steps(1).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stop_on_plateau() {
+        let mut s = Search::default();
+        assert!(s.push(probe(1, 100f64, 10)));
+        assert!(s.push(probe(2, 190f64, 11)));
+        assert!(!s.push(probe(4, 195f64, 12)));
+        assert_eq!(2, s.recommended().unwrap().parallel);
+    }
+
+    #[test]
+    fn stop_on_latency_degradation() {
+        let mut s = Search::default();
+        assert!(s.push(probe(1, 100f64, 10)));
+        assert!(s.push(probe(2, 190f64, 15)));
+        assert!(!s.push(probe(4, 380f64, 25)));
+        assert_eq!(2, s.recommended().unwrap().parallel);
+    }
+}
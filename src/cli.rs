@@ -0,0 +1,1845 @@
+use crate::{
+    aggregate,
+    bench::{
+        build_runtime, measure, perform_requests, Concurrency, ErrorWindow, Load, Observer, Run,
+        Warmup, MIN_STACK_SIZE,
+    },
+    body::{self, Body, SizedBody},
+    capacity,
+    compare::{self, MannWhitney, Slo},
+    config, diff,
+    duration::{format_duration, DurationRange, DurationUnit},
+    generator::BodyGenerator,
+    output::{
+        self, get_output_file, get_output_file_truncated, write_csv, write_slow_log, CsvStream,
+        RotatingWriter, Rotation, SlowAlert, StatusPattern,
+    },
+    overhead::Overhead,
+    prometheus,
+    rate::TargetRps,
+    replay,
+    request::{self, Client, ClientConfig, Failure, TlsVersion},
+    scenario::{Scenario, Target},
+    size::{self, SizeRange},
+    stats::{
+        self, Confidence, Delivery, Distribution, ErrorReason, Multiplexing, Phases, ServerTime,
+        Stats, Transfer, Trimmed,
+    },
+    tls::Resumption,
+    token::TokenSource,
+    urls,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::{ArgAction, CommandFactory, Parser, Subcommand};
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Read},
+    num::{NonZeroU32, NonZeroUsize},
+    ops::Add,
+    process::ExitCode,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// A tiny HTTP benchmarking and performance testing tool.
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    args_override_self = true
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The URL to be requested
+    #[arg(required_unless_present_any = ["scenario", "urls_file", "self_benchmark"])]
+    url: Option<String>,
+
+    /// Reads default values of the flags from the JSON file, e.g.
+    /// '{"parallel": 8, "header": ["X-Env: staging"]}'; can be passed
+    /// multiple times, so that later files override earlier ones, while
+    /// flags which can be passed multiple times are appended; flags
+    /// passed via the command line override all files
+    #[arg(long, value_name = "FILE")]
+    config: Vec<String>,
+
+    /// Reads one URL per line from the file and distributes the requests
+    /// cyclically across them; blank lines and lines starting with '#'
+    /// are skipped
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["url", "scenario"])]
+    urls_file: Option<String>,
+
+    /// Removes duplicate URLs read from `urls-file`, keeping the first
+    /// occurrence of each URL
+    #[arg(long, requires = "urls_file")]
+    dedup_urls: bool,
+
+    /// Simulates the given amount of users, to which the requests belong
+    /// in turn, and routes all requests of a user to the same URL of
+    /// `urls-file` via consistent hashing, e.g. to test session affinity;
+    /// reports how the users spread across the URLs
+    #[arg(
+        long,
+        value_name = "USERS",
+        requires = "urls_file",
+        conflicts_with = "raw_path"
+    )]
+    sticky: Option<NonZeroUsize>,
+
+    /// Sends the key of the user of each request, e.g. 'user-7', in the
+    /// given header
+    #[arg(long, value_name = "NAME", requires = "sticky")]
+    sticky_header: Option<String>,
+
+    /// The scheme prepended to URLs without a scheme, e.g. 'example.com';
+    /// such URLs are rejected by default
+    #[arg(long, value_name = "SCHEME", value_parser = ["http", "https"])]
+    default_scheme: Option<String>,
+
+    /// Sends the paths of the URLs exactly as given, e.g. '/a/../b' or
+    /// '/%2e%2e/b', instead of removing dot segments and normalizing the
+    /// encoding; responses are not decompressed, redirects are not
+    /// followed and the timeout only covers the response headers. Only
+    /// send raw paths to servers you are authorized to test; see the
+    /// README
+    #[arg(long, conflicts_with_all = ["chunked", "cache_bust"])]
+    raw_path: bool,
+
+    /// Reads a JSON scenario file containing a list of `targets`, which
+    /// are benchmarked concurrently; each target requires an `url` and
+    /// may set its own `name`, `method`, `headers`, `body`, `count` and
+    /// `parallel` values, which otherwise fall back to the passed flags
+    #[arg(long, conflicts_with_all = ["url", "summary_json", "baseline"])]
+    scenario: Option<String>,
+
+    /// Reads an OpenAPI 3 or Swagger 2 spec in the JSON format and
+    /// benchmarks each operation as a target like in a `scenario`, with
+    /// the URL as base URL of the paths; parameters and bodies are filled
+    /// in with the example values of the spec or values generated from
+    /// the schemas
+    #[cfg(feature = "openapi")]
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "scenario", "urls_file", "body", "body_file", "summary_json", "baseline", "replay",
+            "find_capacity", "target_rps"
+        ]
+    )]
+    openapi: Option<String>,
+
+    /// The HTTP method to be used
+    #[arg(short = 'X', long, default_value = "GET")]
+    method: String,
+
+    /// A comma separated list of HTTP methods which are used
+    /// cyclically in the given order for consecutive requests;
+    /// overwrites `method`, if set
+    #[arg(long, value_delimiter = ',')]
+    method_cycle: Vec<String>,
+
+    /// The body sent with each request of the given method in the format
+    /// 'METHOD=BODY', e.g. 'POST={"id":1}', instead of `body`; can be
+    /// passed once per method, so that e.g. only POST and PUT requests
+    /// carry a body
+    #[arg(long, value_name = "METHOD=BODY", value_parser = parse_method_body)]
+    method_body: Vec<(String, String)>,
+
+    /// The HTTP headers to be sent with the request;
+    /// format is 'key: value'
+    #[arg(short = 'H', long)]
+    header: Vec<String>,
+
+    /// Reads headers in the format 'key: value' from the file, one per
+    /// line; blank lines and lines starting with '#' are skipped and
+    /// headers passed via `header` take precedence
+    #[arg(long, value_name = "FILE")]
+    headers_file: Option<String>,
+
+    /// The value of the Host header sent with each request, which takes
+    /// precedence over the host of the URL and any Host header passed
+    /// via `header`
+    #[arg(long)]
+    host: Option<String>,
+
+    /// The body content to be sent with the request
+    #[arg(short, long)]
+    body: Option<String>,
+
+    /// Encodes `body` in the given charset, e.g. 'Shift_JIS', instead of
+    /// UTF-8 and declares it in the Content-Type header, which defaults
+    /// to 'text/plain'
+    #[arg(
+        long,
+        value_name = "ENCODING",
+        requires = "body",
+        conflicts_with_all = ["body_file", "template", "scenario"]
+    )]
+    body_charset: Option<String>,
+
+    /// Appends a query parameter with a random value to each request
+    /// to bypass caches; the parameter name defaults to '_'
+    #[arg(long, value_name = "PARAM", num_args = 0..=1, default_missing_value = "_")]
+    cache_bust: Option<String>,
+
+    /// Reads the contents of the file and uses it as body
+    /// for the request; overwrites `body`, if both set
+    #[arg(short = 'f', long)]
+    body_file: Option<String>,
+
+    /// Sends each non-empty line of `body-file` as the body of a
+    /// separate request; `count` defaults to the amount of lines
+    #[arg(long, requires = "body_file")]
+    repeat_body_file_per_line: bool,
+
+    /// Replaces each occurrence of '{{n}}' in the body with the index
+    /// of the request; bodies which are not valid UTF-8 are sent as is
+    #[arg(long)]
+    template: bool,
+
+    /// Renders the contents of the file as Handlebars template for each
+    /// request; see the README for the available variables
+    #[cfg(feature = "body-template")]
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["body", "body_file", "template", "random_body", "random_body_size"]
+    )]
+    body_template: Option<String>,
+
+    /// Generates a JSON body for each request from the given spec, in
+    /// which values like '$int:1:100' or '$string:8' are replaced with
+    /// random values derived from `seed`; see the README for all
+    /// generators
+    #[arg(long, value_name = "SPEC", conflicts_with_all = ["body", "body_file", "template"])]
+    random_body: Option<String>,
+
+    /// Sends a body of a random size within the given range with each
+    /// request, e.g. '1KiB..10MiB', and reports the upload bandwidth; the
+    /// bodies are filled with random bytes derived from `seed`
+    #[arg(
+        long,
+        value_name = "MIN..MAX",
+        conflicts_with_all = ["body", "body_file", "template", "random_body"]
+    )]
+    random_body_size: Option<SizeRange>,
+
+    /// Fills the bodies of `random-body-size` with zero bytes instead of
+    /// random bytes, which are cheaper to generate
+    #[arg(long, requires = "random_body_size")]
+    zero_body: bool,
+
+    /// Sends the ETag of the last response which had one in the
+    /// If-None-Match header of subsequent requests and reports the share
+    /// of these requests answered with 304 Not Modified; the ETag is
+    /// shared by all URLs
+    #[arg(long)]
+    conditional: bool,
+
+    /// Reads and discards the full response body of each request, so
+    /// that the measured time includes the whole transfer; by default,
+    /// only the status and headers are awaited and connections with
+    /// a pending response body can not be reused
+    #[arg(long)]
+    drain_body: bool,
+
+    /// Stops reading a response body after the given size, e.g. '10MiB',
+    /// and marks the response as truncated; the sizes and transfer times
+    /// of truncated responses only cover the bytes read and they never
+    /// match `golden`; only applies if the bodies are read, e.g. via
+    /// `drain-body`
+    #[arg(long, value_name = "SIZE", value_parser = size::parse_size)]
+    max_response_bytes: Option<u64>,
+
+    /// Reads the contents of the file and fails if the body of any
+    /// response does not equal it; implies `drain-body`
+    #[arg(long)]
+    golden: Option<String>,
+
+    /// Prints a line based diff between the golden file and the first
+    /// mismatching response body
+    #[arg(long, requires = "golden")]
+    golden_diff: bool,
+
+    /// The amount of requests which will be sent [default: 1]
+    #[arg(short, long, visible_short_alias = 'n')]
+    count: Option<NonZeroU32>,
+
+    /// Sends requests until the given duration has elapsed instead of
+    /// sending `count` requests
+    #[arg(long, conflicts_with_all = ["count", "find_capacity"])]
+    duration: Option<humantime::Duration>,
+
+    /// Gives requests in flight at the end of `duration` up to the given
+    /// time to complete; requests which are still in flight afterwards
+    /// are aborted and discarded instead of being counted as timeouts.
+    /// By default, all requests in flight are awaited
+    #[arg(long, requires = "duration")]
+    drain: Option<humantime::Duration>,
+
+    /// Disables the transparent decompression of gzip, brotli and
+    /// deflate encoded responses, so that the sizes and transfer times
+    /// of the raw bodies are measured; the amount of encoded responses
+    /// is reported
+    #[arg(long)]
+    no_decompress: bool,
+
+    /// The value of the Accept-Encoding header, which otherwise lists
+    /// the encodings which are decompressed; 'none' omits the header and
+    /// disables the decompression
+    #[arg(long, value_name = "VALUE")]
+    accept_encoding: Option<String>,
+
+    /// Sends requests until the response bodies add up to the given size
+    /// (e.g. '500MB' or '1GiB') instead of sending `count` requests and
+    /// reports the achieved bandwidth; implies `drain-body`
+    #[arg(long, value_name = "SIZE", value_parser = size::parse_size, conflicts_with_all = ["count", "duration"])]
+    total_bytes: Option<u64>,
+
+    /// Reads the timestamps of a CSV file previously written via `csv`
+    /// or `output` and sends one request at the same offset from the
+    /// start of the run as each recorded request; see the README
+    #[arg(long, value_name = "CSV", conflicts_with_all = ["count", "duration", "total_bytes", "wait", "scenario", "find_capacity"])]
+    replay: Option<String>,
+
+    /// The maximum amount of requests which will be sent
+    /// concurrently at a given time
+    #[arg(short, long, default_value = "1")]
+    parallel: NonZeroUsize,
+
+    /// Searches the concurrency which maximizes the throughput by
+    /// running probes with doubling `parallel` values until the 95th
+    /// percentile latency doubles compared to the first probe, the
+    /// throughput gain drops below 5% or `max-parallel` is reached;
+    /// each probe sends `count` requests, defaulting to 100
+    #[arg(long, conflicts_with_all = ["scenario", "csv", "output", "summary_json", "baseline", "prometheus"])]
+    find_capacity: bool,
+
+    /// Measures the overhead of rush itself by sending `count` requests,
+    /// defaulting to 10000, one after another to a local server and
+    /// reports the time per request spent outside of the measured
+    /// latencies, the cost of taking the timestamps and the lowest
+    /// measurable latency, e.g. to judge sub-millisecond results
+    #[arg(long, conflicts_with_all = ["url", "scenario", "urls_file", "find_capacity"])]
+    self_benchmark: bool,
+
+    /// The maximum concurrency probed by `find-capacity` or reached by
+    /// `target-rps`
+    #[arg(long, default_value = "128")]
+    max_parallel: NonZeroUsize,
+
+    /// Sends the given amount of requests per second, starting with
+    /// `parallel` requests in flight and adjusting the concurrency once
+    /// per second to the latency, up to `max-parallel`; reports the
+    /// concurrency over time and whether the target was sustained
+    #[arg(long, value_name = "N", value_parser = parse_rate, conflicts_with_all = ["scenario", "find_capacity", "replay", "total_bytes"])]
+    target_rps: Option<f64>,
+
+    /// Sends the requests in bursts of `parallel` requests, each sent at
+    /// once after the previous burst has completed, multiplexed over a
+    /// single HTTP/2 connection per host without negotiating the
+    /// protocol; reports the requests per connection to confirm that
+    /// they were multiplexed
+    #[arg(long, conflicts_with_all = ["target_rps", "replay", "raw_path", "tls_resumption", "find_capacity"])]
+    multiplex: bool,
+
+    /// Sends the requests via HTTP/3 over QUIC without negotiating the
+    /// protocol, so that the server has to support it; requires building
+    /// with `RUSTFLAGS="--cfg reqwest_unstable"`
+    #[cfg(feature = "http3")]
+    #[arg(long, conflicts_with_all = ["multiplex", "raw_path", "tls_resumption"])]
+    http3: bool,
+
+    /// Records the status and URL of each redirect followed by each
+    /// request, up to 10 per request, and reports the most common
+    /// redirect chains
+    #[arg(long, conflicts_with = "raw_path")]
+    trace_redirects: bool,
+
+    /// The stack size of each worker thread in bytes, e.g. to render
+    /// deeply nested body templates; must be at least 65536 [default:
+    /// 2 MiB, or `RUST_MIN_STACK` if set]
+    #[arg(long, value_name = "BYTES", value_parser = parse_stack_size)]
+    stack_size: Option<usize>,
+
+    /// Waits until the given point in time (RFC 3339, e.g.
+    /// '2024-01-01T12:00:00Z') before the measured requests are sent,
+    /// which allows synchronizing multiple instances; warmup requests
+    /// are sent before waiting
+    #[arg(long)]
+    start_at: Option<String>,
+
+    /// Perform warmup requests which do not count to the benchmark result
+    #[arg(long)]
+    warmup: Option<u32>,
+
+    /// Sends warmup requests in batches of `stable-window` requests until
+    /// the coefficient of variation of the latencies of a batch is at most
+    /// `stable-cov`; `warmup` sets the maximum amount of warmup requests
+    /// and defaults to 1000
+    #[arg(long, conflicts_with = "find_capacity")]
+    warmup_until_stable: bool,
+
+    /// The size of the batches of `warmup-until-stable`
+    #[arg(long, default_value = "20", requires = "warmup_until_stable")]
+    stable_window: NonZeroU32,
+
+    /// The coefficient of variation at which `warmup-until-stable`
+    /// considers the latencies stable
+    #[arg(long, default_value = "0.1", requires = "warmup_until_stable")]
+    stable_cov: f64,
+
+    /// A duration awaited before a request is sent; you can pass
+    /// a range (format: 'from..to', e.g. '10ms..20ms') from which
+    /// a random duration will be picked, or a think-time distribution,
+    /// either exponential with the given mean (e.g. 'exp:500ms') or
+    /// log-normal with the given median and shape (e.g.
+    /// 'lognormal:500ms:1.5')
+    #[arg(short, long)]
+    wait: Option<DurationRange>,
+
+    /// The timeout of each request until it has been completed; you can
+    /// pass a range (format: 'from..to', e.g. '1s..5s') from which a
+    /// random timeout will be picked for each request [default: 30s]
+    #[arg(short, long)]
+    timeout: Option<DurationRange>,
+
+    /// Resolves the host explicitly before each request and reports
+    /// the lookup durations; the lookup is not included in the measured
+    /// request duration
+    #[arg(long)]
+    measure_dns: bool,
+
+    /// Writes the results of each request formatted as CSV to
+    /// the given output directory; appends the file if it already
+    /// exists. If the path is a FIFO, the run waits for a reader and
+    /// each result is written as soon as the request completes
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Rotates the `output` file before it exceeds the given size (e.g.
+    /// '100MB'); the full file is renamed by appending the next free
+    /// number, e.g. 'out.csv.1', so that higher numbers are newer
+    #[arg(long, value_name = "SIZE", value_parser = size::parse_size, requires = "output")]
+    output_rotate_size: Option<u64>,
+
+    /// Rotates the `output` file once its records span the given
+    /// duration (e.g. '1h'), named like for `output-rotate-size`
+    #[arg(long, value_name = "DURATION", requires = "output")]
+    output_rotate_interval: Option<humantime::Duration>,
+
+    /// Writes the results of the requests which took longer than
+    /// `slow-threshold` formatted as CSV to the given file, followed by
+    /// the values of the captured headers; appends the file if it
+    /// already exists
+    #[arg(long, requires = "slow_threshold")]
+    slow_log: Option<String>,
+
+    /// The duration above which a request is written to the `slow-log`
+    /// or reported by `alert`
+    #[arg(long)]
+    slow_threshold: Option<humantime::Duration>,
+
+    /// Writes a warning to stderr as soon as a request took longer than
+    /// `slow-threshold`, including its status and duration; at most one
+    /// warning is written per second, which reports the amount of slow
+    /// requests since the previous one
+    #[arg(long, requires = "slow_threshold", conflicts_with = "silent")]
+    alert: bool,
+
+    /// Captures the value of the given response header for the
+    /// `slow-log`; can be passed multiple times
+    #[arg(long, value_name = "NAME")]
+    capture_header: Vec<String>,
+
+    /// Reads the processing time the server reports in the given
+    /// response header (e.g. 'X-Response-Time' or 'Server-Timing') and
+    /// prints it next to the observed latency; values without a unit
+    /// are taken as milliseconds
+    #[arg(long, value_name = "NAME")]
+    server_time_header: Option<String>,
+
+    /// Writes the results of each request to the given file in the
+    /// Parquet format; overwrites the file if it already exists
+    #[cfg(feature = "parquet")]
+    #[arg(long)]
+    parquet: Option<String>,
+
+    /// Writes a heatmap of the latencies over time to the given SVG
+    /// file, coloring each cell of time columns and latency rows by the
+    /// amount of requests within; overwrites the file if it already
+    /// exists
+    #[cfg(feature = "heatmap")]
+    #[arg(long, value_name = "PATH")]
+    heatmap: Option<String>,
+
+    /// Appends the run, including its summary and the results of each
+    /// request, to the SQLite database at the given path
+    #[cfg(feature = "sqlite")]
+    #[arg(long)]
+    sqlite: Option<String>,
+
+    /// Only writes the results of requests matching any of the given
+    /// comma separated status codes (e.g. '404'), classes (e.g. '5xx')
+    /// or kinds of failures ('dns', 'connect', 'tls', 'timeout' or
+    /// 'error') to the per request outputs; the statistics still cover
+    /// all requests
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    only_status: Vec<StatusPattern>,
+
+    /// Orders the per request outputs by the dispatch index of the
+    /// requests instead of their timestamps, which may differ between
+    /// concurrent requests, and writes the index as last CSV column
+    #[arg(long)]
+    ordered: bool,
+
+    /// Prints the results of each request to stdout CSV formatted;
+    /// bypasses `silent`, if set
+    #[arg(long)]
+    csv: bool,
+
+    /// Prints the summary as Markdown tables of the latencies and the
+    /// status codes instead of the aligned text, e.g. to paste it into an
+    /// issue
+    #[arg(long, conflicts_with = "csv")]
+    markdown: bool,
+
+    /// Writes the summarized statistics of the run formatted as
+    /// JSON to the given file; durations are given in nanoseconds
+    #[arg(long)]
+    summary_json: Option<String>,
+
+    /// Fails the run if a latency metric exceeds the given duration,
+    /// e.g. 'p95=200ms'; the metric is one of 'avg', 'median', 'p90',
+    /// 'p95', 'p99' and 'max'; can be passed multiple times
+    #[arg(long, value_name = "METRIC=DURATION")]
+    slo: Vec<Slo>,
+
+    /// Compares the results of the run against a summary previously
+    /// saved via `summary-json` and fails if any latency metric
+    /// exceeds the baseline by more than `regression-threshold`
+    #[arg(long)]
+    baseline: Option<String>,
+
+    /// The tolerated increase of a latency metric compared to the
+    /// `baseline` in percent
+    #[arg(long, default_value = "10")]
+    regression_threshold: f64,
+
+    /// Saves the latency of each request in the `summary-json`, so that
+    /// the significance of a difference can be tested when comparing
+    /// against the summary
+    #[arg(long, requires = "summary_json")]
+    summary_samples: bool,
+
+    /// The significance level of the Mann-Whitney U test, which is run
+    /// against a `baseline` saved with `summary-samples`
+    #[arg(long, default_value = "0.05")]
+    alpha: f64,
+
+    /// The minimum amount of samples below which a warning is printed
+    /// that the percentiles are unreliable, unless a smaller `count` is
+    /// passed
+    #[arg(long, default_value = "30")]
+    min_samples: usize,
+
+    /// Prints all durations of the summary in the given unit, either
+    /// 'ns', 'us', 'ms' or 's', instead of the unit fitting each value,
+    /// so that the values line up
+    #[arg(long)]
+    unit: Option<DurationUnit>,
+
+    /// Prints the skewness and kurtosis of the measured latencies
+    /// and a rough classification of their distribution
+    #[arg(long)]
+    distribution: bool,
+
+    /// Prints the average durations of the phases of the requests as a
+    /// waterfall; the DNS lookup requires `measure-dns` and the transfer
+    /// of the body `drain-body`, and TTFB includes connect and TLS until
+    /// those phases are measured
+    #[arg(long)]
+    phases: bool,
+
+    /// Prints the times to the first and to the last byte of the
+    /// responses and the transfer of the body in between; implies
+    /// `drain-body`
+    #[arg(long)]
+    ttlb: bool,
+
+    /// Additionally prints the average and standard deviation after
+    /// removing the given percentage of the fastest and the slowest
+    /// latencies each, which reduces the impact of outliers
+    #[arg(long, value_name = "PCT", value_parser = parse_trim_percentage)]
+    trim: Option<f64>,
+
+    /// Prints 95% confidence intervals of the average and the key
+    /// percentiles computed by bootstrap resampling the latencies using
+    /// `seed`
+    #[arg(long)]
+    ci: bool,
+
+    /// The amount of resamples drawn by `ci`
+    #[arg(long, default_value = "1000", requires = "ci")]
+    ci_iterations: usize,
+
+    /// Writes the summarized statistics of the run to the given file
+    /// in the Prometheus text exposition format, e.g. to be picked up
+    /// by the node_exporter textfile collector
+    #[arg(long)]
+    prometheus: Option<String>,
+
+    /// Shows a live dashboard of the throughput, the latency
+    /// percentiles and the statuses while the requests are sent
+    #[cfg(feature = "tui")]
+    #[arg(long, conflicts_with_all = ["silent", "find_capacity", "alert"])]
+    tui: bool,
+
+    /// Do not print any output
+    #[arg(short, long)]
+    silent: bool,
+
+    /// Exit with a non-zero code if any request did not succeed, i.e.
+    /// failed to be sent or was answered with a 4xx or 5xx status;
+    /// combine with `silent` to use rush as a bare health check
+    #[arg(long)]
+    check: bool,
+
+    /// Like `check`, but also exits with a non-zero code if any response
+    /// has a status other than 2xx, e.g. 304, which is not followed like
+    /// a redirect
+    #[arg(long)]
+    check_2xx: bool,
+
+    /// Sends the request bodies with chunked transfer encoding instead
+    /// of a Content-Length header
+    #[arg(long)]
+    chunked: bool,
+
+    /// Fails each request whose body is expected to differ between
+    /// requests, e.g. via '{{n}}' in a `template` or '$uuid' in a
+    /// `random-body`, but equals the body of another request, which
+    /// guards against rendered bodies being reused; constant bodies,
+    /// e.g. via `zero-body`, are not checked
+    #[arg(long, conflicts_with = "chunked")]
+    no_body_reuse: bool,
+
+    /// Executes the shell command and sends its output as bearer token
+    /// in the Authorization header; the command is executed again and
+    /// the request retried once when a request is answered with 401
+    #[arg(long, value_name = "CMD")]
+    token_command: Option<String>,
+
+    /// Signs each request with the AWS Signature Version 4 for the given
+    /// region and service (e.g. 'us-east-1:execute-api') using the
+    /// credentials from the AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and
+    /// AWS_SESSION_TOKEN environment variables
+    #[cfg(feature = "aws-sigv4")]
+    #[arg(
+        long,
+        value_name = "REGION:SERVICE",
+        conflicts_with_all = ["token_command", "raw_path"]
+    )]
+    aws_sigv4: Option<String>,
+
+    /// Monitors the outcomes of the given amount of most recent requests
+    /// and aborts the run as soon as their error rate exceeds
+    /// `max-error-rate`
+    #[arg(long, value_name = "N", requires = "max_error_rate")]
+    error_rate_window: Option<NonZeroUsize>,
+
+    /// The error rate in percent within the `error-rate-window` above
+    /// which the run is aborted
+    #[arg(long, value_name = "PCT", value_parser = parse_percentage, requires = "error_rate_window")]
+    max_error_rate: Option<f64>,
+
+    /// Marks the given percentage of successful responses as failed
+    /// without actually failing the requests, e.g. to verify alerting
+    /// on error rates; injected failures are labeled in the output
+    #[arg(long, value_name = "PCT", value_parser = parse_percentage)]
+    inject_errors: Option<f64>,
+
+    /// The seed for random decisions which are reproducible between
+    /// runs, such as `inject-errors` and `ci`; defaults to a random seed
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Disable TLS certificate invalidation
+    #[arg(short, long)]
+    insecure: bool,
+
+    /// The oldest TLS version accepted for connections, either '1.2' or
+    /// '1.3'
+    #[arg(long, value_name = "VERSION")]
+    tls_min_version: Option<TlsVersion>,
+
+    /// The newest TLS version offered for connections, either '1.2' or
+    /// '1.3'
+    #[arg(long, value_name = "VERSION")]
+    tls_max_version: Option<TlsVersion>,
+
+    /// Send each request over a new connection whose TLS handshake is
+    /// either a full one ('off') or resumes a previous session ('force'),
+    /// and report the amount of resumed handshakes; HTTPS URLs only
+    #[arg(long, value_name = "MODE", conflicts_with = "raw_path")]
+    tls_resumption: Option<Resumption>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compares two summaries previously saved via `summary-json`
+    ///
+    /// No requests are sent; fails if any latency metric of the current
+    /// summary exceeds the baseline by more than `regression-threshold`.
+    Compare {
+        /// The summary of the baseline run
+        baseline: String,
+
+        /// The summary of the current run
+        current: String,
+
+        /// The tolerated increase of a latency metric compared to the
+        /// baseline in percent
+        #[arg(long, default_value = "10")]
+        regression_threshold: f64,
+
+        /// The significance level of the Mann-Whitney U test, which is
+        /// run if both summaries contain their samples
+        #[arg(long, default_value = "0.05")]
+        alpha: f64,
+    },
+    /// Aggregates the summaries of several runs saved via `summary-json`
+    ///
+    /// No requests are sent; reports the median, the range and the
+    /// standard deviation of each latency metric across the runs, e.g. the
+    /// median and the worst 99th percentile.
+    Aggregate {
+        /// The summaries of the runs
+        #[arg(required = true, num_args = 2..)]
+        summaries: Vec<String>,
+    },
+}
+
+/// The exit codes of the outcomes of a run, so that scripts can branch on
+/// the reason a run failed; errors, e.g. invalid arguments, exit with 1.
+#[derive(Debug, Clone, Copy)]
+enum Exit {
+    Success = 0,
+    Error = 1,
+    /// A latency metric exceeded its SLO.
+    Slo = 2,
+    /// Requests failed, either exceeding `max-error-rate` or any at all
+    /// with `check`.
+    ErrorRate = 3,
+    /// A response body did not match the golden file.
+    Assertion = 4,
+    /// A latency metric regressed compared to the baseline.
+    Regression = 5,
+}
+
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
+
+/// Runs rush with the arguments of the process, preceded by those of its
+/// config file.
+pub fn run() -> Result<ExitCode> {
+    let args = match Args::try_parse_from(args_with_config()?) {
+        Ok(args) => args,
+        // clap exits with 2 on invalid arguments, which is the exit code
+        // of SLO breaches.
+        Err(err) => {
+            let _ = err.print();
+            return Ok(if err.use_stderr() {
+                Exit::Error
+            } else {
+                Exit::Success
+            }
+            .into());
+        }
+    };
+
+    if let Some(Command::Compare {
+        baseline,
+        current,
+        regression_threshold,
+        alpha,
+    }) = &args.command
+    {
+        return compare_summaries(baseline, current, *regression_threshold, *alpha);
+    }
+
+    if let Some(Command::Aggregate { summaries }) = &args.command {
+        return aggregate_summaries(summaries);
+    }
+
+    if args.self_benchmark {
+        let overhead = Overhead::measure(args.count.map(NonZeroU32::get).unwrap_or(10_000))?;
+        if !args.silent {
+            overhead.print();
+        }
+        return Ok(Exit::Success.into());
+    }
+
+    if args.slow_threshold.is_some() && args.slow_log.is_none() && !args.alert {
+        anyhow::bail!("`slow-threshold` requires `slow-log` or `alert`");
+    }
+
+    let clock_resolution = request::clock_resolution();
+    if clock_resolution > request::COARSE_CLOCK && !args.silent && !args.csv {
+        println!(
+            "warning: the monotonic clock has a resolution of {:.1}, so that shorter latencies \
+            cannot be measured accurately",
+            format_duration(clock_resolution)
+        );
+    }
+
+    let wait = match args.wait {
+        Some(v) if v.start() == &Duration::from_millis(0) && v.is_flat() => None,
+        v => v,
+    };
+
+    let start_at = match args
+        .start_at
+        .map(|v| {
+            DateTime::parse_from_rfc3339(&v)
+                .map_err(|err| anyhow::anyhow!("invalid `start-at` timestamp: {err}"))
+        })
+        .transpose()?
+    {
+        Some(v) if v <= Utc::now() => {
+            if !args.silent {
+                println!("warning: `start-at` lies in the past; starting immediately.");
+            }
+            None
+        }
+        v => v.map(|v| v.with_timezone(&Utc)),
+    };
+
+    let timeout = args.timeout;
+
+    let mut charset = None;
+    let bodies = match (args.body_file, args.body) {
+        (Some(path), _) if args.repeat_body_file_per_line => read_bodies_per_line(&path)?,
+        (Some(path), _) => vec![read_body_from_file(&path)?],
+        (None, Some(body)) => match &args.body_charset {
+            Some(label) => {
+                let (body, name) = body::encode(&body, label)
+                    .map_err(|err| anyhow::anyhow!("invalid `body-charset`: {err}"))?;
+                charset = Some(name);
+                vec![body]
+            }
+            None => vec![body.into_bytes()],
+        },
+        (None, None) => vec![],
+    };
+
+    let bodies: Vec<_> = bodies
+        .into_iter()
+        .map(|b| Body::new(b, args.template))
+        .collect();
+
+    #[cfg(feature = "body-template")]
+    let bodies = match &args.body_template {
+        Some(path) => {
+            let source = String::from_utf8(read_body_from_file(path)?)?;
+            let template = crate::template::BodyTemplate::new(&source)?;
+            vec![Body::Handlebars(std::sync::Arc::new(template))]
+        }
+        None => bodies,
+    };
+
+    if args.template && !args.silent && bodies.iter().any(|b| !b.is_template()) {
+        println!(
+            "warning: the body is not valid UTF-8 and will be sent without applying the template"
+        );
+    }
+
+    let golden = args
+        .golden
+        .as_deref()
+        .map(read_body_from_file)
+        .transpose()?;
+
+    let count = args.count.map(NonZeroU32::get).unwrap_or_else(|| {
+        if args.repeat_body_file_per_line {
+            bodies.len() as u32
+        } else {
+            1
+        }
+    });
+    // The warning about too few samples is only noise if a smaller count
+    // has been passed explicitly.
+    let min_samples = match args.count {
+        Some(count) if (count.get() as usize) < args.min_samples => 0,
+        _ => args.min_samples,
+    };
+
+    let baseline = args.baseline.map(compare::read_stats).transpose()?;
+
+    let seed = args.seed.unwrap_or_else(rand::random);
+
+    let bodies = match &args.random_body {
+        Some(spec) => {
+            let generator = BodyGenerator::new(spec, seed)
+                .map_err(|err| anyhow::anyhow!("invalid `random-body`: {err}"))?;
+            vec![Body::Generated(Arc::new(generator))]
+        }
+        None => bodies,
+    };
+    let bodies = match args.random_body_size {
+        Some(size) => vec![Body::Sized(SizedBody {
+            size,
+            zeroed: args.zero_body,
+            seed,
+        })],
+        None => bodies,
+    };
+    if args.inject_errors.is_some() && !args.silent {
+        println!("warning: injecting synthetic errors using the seed {seed}");
+    }
+    if args.raw_path && !args.silent {
+        println!(
+            "warning: redirects are not followed with `raw-path` and the timeout only covers \
+            the response headers, not the body"
+        );
+    }
+
+    let schedule: Option<Arc<[_]>> = args
+        .replay
+        .as_deref()
+        .map(replay::read_schedule)
+        .transpose()
+        .map_err(|err| anyhow::anyhow!("invalid `replay`: {err}"))?
+        .map(Into::into);
+
+    let token = args
+        .token_command
+        .clone()
+        .map(TokenSource::new)
+        .transpose()?
+        .map(Arc::new);
+
+    #[cfg(feature = "aws-sigv4")]
+    let signer = args
+        .aws_sigv4
+        .as_deref()
+        .map(|spec| crate::sigv4::Signer::new(spec, crate::sigv4::Credentials::from_env()?))
+        .transpose()
+        .map_err(|err| anyhow::anyhow!("invalid `aws-sigv4`: {err}"))?
+        .map(Arc::new);
+
+    let method_bodies: Vec<_> = args
+        .method_body
+        .iter()
+        .map(|(method, body)| {
+            (
+                method.clone(),
+                Body::new(body.clone().into_bytes(), args.template),
+            )
+        })
+        .collect();
+
+    // The targets of a scenario or a spec are reported separately.
+    #[cfg(not(feature = "openapi"))]
+    let per_target = args.scenario.is_some();
+    #[cfg(feature = "openapi")]
+    let per_target = args.scenario.is_some() || args.openapi.is_some();
+
+    if args.no_body_reuse
+        && !per_target
+        && !bodies
+            .iter()
+            .chain(method_bodies.iter().map(|(_, b)| b))
+            .any(Body::varies)
+    {
+        anyhow::bail!(
+            "`no-body-reuse` requires a body which differs between requests, e.g. via '{{{{n}}}}' \
+            in a `template`, '$n' or '$uuid' in a `random-body` or `random-body-size` with \
+            random contents of at least 8 bytes"
+        );
+    }
+
+    let methods = if args.method_cycle.is_empty() {
+        vec![args.method]
+    } else {
+        args.method_cycle
+    };
+
+    let urls = match &args.urls_file {
+        Some(path) => {
+            let mut urls = urls::read_urls(path)?;
+            if args.dedup_urls {
+                let removed = urls::dedup(&mut urls);
+                if !args.silent && !args.csv {
+                    println!("Removed {removed} duplicate URLs");
+                }
+            }
+            urls
+        }
+        None => vec![],
+    };
+
+    let mut headers = match &args.headers_file {
+        Some(path) => {
+            let content = fs::read_to_string(path)?;
+            let mut headers = request::parse_headers(&content)
+                .map_err(|err| anyhow::anyhow!("invalid headers file '{path}': {err}"))?;
+            headers.extend(args.header.iter().cloned());
+            headers
+        }
+        None => args.header.clone(),
+    };
+    if let Some(charset) = charset {
+        request::set_charset(&mut headers, charset);
+    }
+
+    let targets = match &args.scenario {
+        Some(path) => Scenario::from_file(path)?.targets,
+        None => vec![Target {
+            url: args.url.unwrap_or_default(),
+            ..Default::default()
+        }],
+    };
+
+    #[cfg(feature = "openapi")]
+    let targets = match &args.openapi {
+        Some(path) => crate::openapi::Spec::from_file(path)?.targets(&targets[0].url)?,
+        None => targets,
+    };
+
+    let runs = targets
+        .into_iter()
+        .map(|target| {
+            let client = Client::new(ClientConfig {
+                urls: if urls.is_empty() {
+                    vec![target.url.clone()]
+                } else {
+                    urls.clone()
+                },
+                default_scheme: args.default_scheme.clone(),
+                methods: target
+                    .method
+                    .clone()
+                    .map(|m| vec![m])
+                    .unwrap_or_else(|| methods.clone()),
+                bodies: target
+                    .body
+                    .clone()
+                    .map(|b| vec![Body::new(b.into_bytes(), args.template)])
+                    .unwrap_or_else(|| bodies.clone()),
+                method_bodies: method_bodies.clone(),
+                headers: headers.iter().chain(&target.headers).cloned().collect(),
+                accept_invalid_certs: args.insecure,
+                tls_min_version: args.tls_min_version,
+                tls_max_version: args.tls_max_version,
+                cache_bust: args.cache_bust.clone(),
+                drain_body: args.drain_body || args.ttlb || args.total_bytes.is_some(),
+                timeout: timeout.clone(),
+                golden: golden.clone(),
+                measure_dns: args.measure_dns,
+                host: args.host.clone(),
+                chunked: args.chunked,
+                no_body_reuse: args.no_body_reuse,
+                token: token.clone(),
+                inject_errors: args.inject_errors,
+                seed,
+                capture_headers: args.capture_header.clone(),
+                server_time_header: args.server_time_header.clone(),
+                no_decompress: args.no_decompress,
+                max_response_bytes: args.max_response_bytes,
+                accept_encoding: args.accept_encoding.clone(),
+                raw_path: args.raw_path,
+                conditional: args.conditional,
+                tls_resumption: args.tls_resumption,
+                sticky: args.sticky,
+                sticky_header: args.sticky_header.clone(),
+                multiplex: args.multiplex,
+                trace_redirects: args.trace_redirects,
+                #[cfg(feature = "aws-sigv4")]
+                aws_sigv4: signer.clone(),
+                #[cfg(feature = "http3")]
+                http3: args.http3,
+            })?;
+
+            let load = match (args.duration, args.total_bytes, &schedule) {
+                (_, _, Some(offsets)) => Load::Replay(offsets.clone()),
+                (Some(duration), _, _) => Load::Duration {
+                    duration: duration.into(),
+                    drain: args.drain.map(Into::into),
+                },
+                (None, Some(bytes), _) => Load::Bytes(bytes),
+                (None, None, None) => {
+                    Load::Count(target.count.map(NonZeroU32::get).unwrap_or(count))
+                }
+            };
+
+            Ok(Run {
+                load,
+                parallel: target.parallel.unwrap_or(args.parallel).get(),
+                stack_size: args.stack_size,
+                error_window: args
+                    .error_rate_window
+                    .zip(args.max_error_rate)
+                    .map(|(size, rate)| Arc::new(ErrorWindow::new(size.get(), rate))),
+                target_rps: args.target_rps.map(|rps| TargetRps {
+                    rps,
+                    initial: args.parallel.get(),
+                    max_parallel: args.max_parallel.get(),
+                }),
+                burst: args.multiplex,
+                target,
+                client: Arc::new(client),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if !args.silent
+        && runs.iter().any(|r| r.parallel > 1)
+        && wait.as_ref().is_some_and(|v| v.is_flat())
+    {
+        println!(
+            "warning: `wait` is set to a fixed duration and `parallel` is set to more than 1. \
+            That means that all requests will wait the same time for each worker. To avoid this, \
+            use a range for `wait`. For example: `-w 900ms..1100ms`."
+        );
+    }
+
+    if args.find_capacity {
+        let run = &runs[0];
+        let probe_count = args.count.map(NonZeroU32::get).unwrap_or(100);
+
+        let runtime = build_runtime(args.max_parallel.get(), args.stack_size)?;
+        if let Some(warmup) = args.warmup.filter(|&w| w > 0) {
+            perform_requests(
+                &runtime,
+                &run.client,
+                run.parallel,
+                warmup,
+                wait.as_ref(),
+                None,
+            );
+        }
+
+        let mut search = capacity::Search::default();
+        for parallel in capacity::Search::steps(args.max_parallel.get()) {
+            let load = Load::Count(probe_count);
+            let result = measure(
+                &runtime,
+                &run.client,
+                &load,
+                Concurrency::Fixed(parallel),
+                wait.as_ref(),
+                None,
+                None,
+            );
+            let Some(stats) = Stats::from_responses(&result.responses, &result.failures) else {
+                break;
+            };
+
+            let probe = capacity::Probe {
+                parallel,
+                throughput: result.throughput(),
+                p95: stats.p95,
+            };
+            if !search.push(probe) {
+                break;
+            }
+        }
+
+        if !args.silent {
+            search.print();
+        }
+
+        return Ok(Exit::Success.into());
+    }
+
+    let warmup = if args.warmup_until_stable {
+        Some(Warmup::UntilStable {
+            window: args.stable_window.get(),
+            max_cov: args.stable_cov,
+            max: args.warmup.unwrap_or(1000),
+        })
+    } else {
+        args.warmup.map(Warmup::Count)
+    };
+
+    #[cfg(feature = "tui")]
+    let live = Arc::new(crate::live::Live::new());
+    #[cfg(feature = "tui")]
+    let done = std::sync::atomic::AtomicBool::new(false);
+
+    // A FIFO is streamed to while the requests complete instead of being
+    // written afterwards, so that it is opened before the run.
+    let stream = match &args.output {
+        Some(path) if output::is_fifo(path) => {
+            if args.ordered {
+                anyhow::bail!("`ordered` cannot be used if `output` is a FIFO");
+            }
+            if args.output_rotate_size.is_some() || args.output_rotate_interval.is_some() {
+                anyhow::bail!("a FIFO passed via `output` cannot be rotated");
+            }
+            if !args.silent && !args.csv {
+                println!("waiting for a reader of the FIFO '{path}'");
+            }
+            let f = output::open_fifo(path)?;
+            Some(Arc::new(CsvStream::new(f, args.only_status.clone())))
+        }
+        _ => None,
+    };
+
+    let mut observers: Vec<Observer> = vec![];
+    if let (true, Some(threshold)) = (args.alert, args.slow_threshold) {
+        let alert = SlowAlert::new(threshold.into());
+        observers.push(Arc::new(
+            move |r: &Result<crate::request::Response, Failure>| alert.record(r),
+        ));
+    }
+    #[cfg(feature = "tui")]
+    if args.tui {
+        let live = live.clone();
+        observers.push(Arc::new(
+            move |r: &Result<crate::request::Response, Failure>| live.record(r),
+        ));
+    }
+    if let Some(stream) = stream.clone() {
+        observers.push(Arc::new(
+            move |r: &Result<crate::request::Response, Failure>| stream.record(r),
+        ));
+    }
+    let observer: Option<Observer> = match observers.len() {
+        0 | 1 => observers.pop(),
+        _ => Some(Arc::new(
+            move |r: &Result<crate::request::Response, Failure>| {
+                observers.iter().for_each(|o| o(r))
+            },
+        )),
+    };
+
+    #[cfg(feature = "sqlite")]
+    let started_at = Utc::now();
+
+    let results = thread::scope(|s| {
+        let handles: Vec<_> = runs
+            .iter()
+            .map(|run| s.spawn(|| run.perform(warmup, start_at, wait.as_ref(), observer.clone())))
+            .collect();
+
+        #[cfg(feature = "tui")]
+        let dashboard = args.tui.then(|| {
+            let total = runs
+                .iter()
+                .map(|r| match &r.load {
+                    Load::Count(n) => *n as u64,
+                    Load::Replay(offsets) => offsets.len() as u64,
+                    Load::Duration { .. } | Load::Bytes(_) => 0,
+                })
+                .sum();
+            let (live, done) = (&live, &done);
+            s.spawn(move || crate::tui::run(live, total, done))
+        });
+
+        let results = handles
+            .into_iter()
+            .map(|h| h.join().expect("run thread panicked"))
+            .collect::<Result<Vec<_>>>();
+
+        #[cfg(feature = "tui")]
+        if let Some(dashboard) = dashboard {
+            done.store(true, std::sync::atomic::Ordering::Relaxed);
+            dashboard.join().expect("dashboard thread panicked")?;
+        }
+
+        results
+    });
+
+    let results = match results {
+        Ok(results) => results,
+        Err(_) if (args.check || args.check_2xx) && args.silent => return Ok(Exit::Error.into()),
+        Err(err) => return Err(err),
+    };
+
+    let run_stats: Vec<_> = results
+        .iter()
+        .map(|r| Stats::from_responses(&r.responses, &r.failures))
+        .collect();
+
+    if let Some(path) = &args.prometheus {
+        let series: Vec<_> = runs
+            .iter()
+            .zip(&results)
+            .zip(&run_stats)
+            .filter_map(|((run, result), stats)| {
+                Some(prometheus::Series {
+                    method: run
+                        .client
+                        .methods()
+                        .iter()
+                        .map(|m| m.as_str())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    url: run
+                        .client
+                        .urls()
+                        .iter()
+                        .map(|u| u.as_str())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    stats: stats.as_ref()?,
+                    throughput: result.throughput(),
+                })
+            })
+            .collect();
+
+        // Write to a temporary file first and move it in place afterwards,
+        // so that collectors never read a partially written file.
+        let tmp_path = format!("{path}.tmp");
+        prometheus::write(get_output_file_truncated(&tmp_path)?, &series)?;
+        fs::rename(tmp_path, path)?;
+    }
+
+    if per_target && !args.csv && !args.silent {
+        for ((run, result), stats) in runs.iter().zip(&results).zip(&run_stats) {
+            if args.markdown {
+                println!("### {}\n", run.target.name());
+            } else {
+                println!(
+                    "Target:     {}\n\
+                    Parallel:   {:>10}  (achieved {:.2})\n",
+                    run.target.name(),
+                    run.parallel,
+                    result.achieved_concurrency(),
+                );
+            }
+            match stats {
+                Some(stats) if args.markdown => stats.print_markdown(args.unit),
+                Some(stats) => stats.print(min_samples, args.unit),
+                None => print_no_results(&result.failures),
+            }
+            println!();
+        }
+    }
+
+    if !args.silent && !args.csv {
+        for s in results.iter().filter_map(|r| r.stabilization) {
+            if s.stable {
+                println!(
+                    "Warmup:     {:>10}  requests until stable (CoV {:.3})",
+                    s.requests, s.cov
+                );
+            } else {
+                println!(
+                    "warning: the latency did not stabilize within {} warmup requests (CoV {:.3})",
+                    s.requests, s.cov
+                );
+            }
+        }
+    }
+
+    let aborted = results.iter().find_map(|r| r.aborted);
+
+    let discarded: usize = results.iter().map(|r| r.discarded).sum();
+    if discarded > 0 && !args.silent && !args.csv {
+        println!(
+            "warning: {discarded} requests were still in flight after the drain period \
+            and have been discarded"
+        );
+    }
+
+    let transfer = results
+        .iter()
+        .filter_map(|r| Some((r.transferred()?, r.elapsed)))
+        .reduce(|(a, a_elapsed), (b, b_elapsed)| (a + b, a_elapsed.max(b_elapsed)))
+        .filter(|_| args.total_bytes.is_some())
+        .map(|(bytes, elapsed)| Transfer {
+            bytes,
+            bandwidth: bytes as f64 / elapsed.as_secs_f64(),
+        });
+
+    let upload = results
+        .iter()
+        .filter_map(|r| Some((r.uploaded()?, r.elapsed)))
+        .reduce(|(a, a_elapsed), (b, b_elapsed)| (a + b, a_elapsed.max(b_elapsed)))
+        .filter(|_| args.random_body_size.is_some())
+        .map(|(bytes, elapsed)| Transfer {
+            bytes,
+            bandwidth: bytes as f64 / elapsed.as_secs_f64(),
+        });
+
+    let reuse: Vec<_> = results
+        .iter()
+        .filter_map(|r| Some((r.connection_reuse()?, r.responses.len() + r.failures.len())))
+        .collect();
+    let connection_reuse = (!reuse.is_empty()).then(|| {
+        let requests: usize = reuse.iter().map(|(_, n)| n).sum();
+        reuse.iter().map(|(v, n)| v * *n as f64).sum::<f64>() / requests.max(1) as f64
+    });
+
+    let handshakes = results.iter().filter_map(|r| r.handshakes).reduce(Add::add);
+
+    let multiplexing = results
+        .iter()
+        .filter(|_| args.multiplex)
+        .filter_map(|r| {
+            Some(Multiplexing {
+                requests: r.responses.len() + r.failures.len(),
+                connections: r.connections?,
+            })
+        })
+        .reduce(|a, b| Multiplexing {
+            requests: a.requests + b.requests,
+            connections: a.connections + b.connections,
+        });
+
+    let target_rate = results.iter().find_map(|r| r.rate.clone());
+
+    let mut res = vec![];
+    let mut failures = vec![];
+    for result in results {
+        res.extend(result.responses);
+        failures.extend(result.failures);
+    }
+    res.sort_by_key(|r| r.timestamp);
+
+    let mut records = output::records(&res, &failures);
+    if args.ordered {
+        // The sort is stable, so that the records of concurrent runs with
+        // the same index remain ordered by their timestamps.
+        records.sort_by_key(|r| r.index);
+    }
+    if !args.only_status.is_empty() {
+        records.retain(|r| args.only_status.iter().any(|p| p.matches(r)));
+    }
+
+    if let Some(err) = stream.as_ref().and_then(|s| s.error()) {
+        if !args.silent {
+            eprintln!("warning: stopped streaming to the FIFO: {err}");
+        }
+    }
+
+    if let Some(path) = args.output.filter(|_| stream.is_none()) {
+        let rotation = Rotation {
+            max_size: args.output_rotate_size,
+            interval: args.output_rotate_interval.map(Into::into),
+        };
+        if rotation.max_size.is_some() || rotation.interval.is_some() {
+            RotatingWriter::open(&path, rotation, args.ordered)?.write(&records)?;
+        } else {
+            let f = get_output_file(&path)?;
+            write_csv(&f, &records, args.ordered)?;
+        }
+    }
+
+    if let (Some(path), Some(threshold)) = (&args.slow_log, args.slow_threshold) {
+        let f = get_output_file(path)?;
+        let captured = args.capture_header.len();
+        write_slow_log(&f, &res, &failures, threshold.into(), captured)?;
+    }
+
+    #[cfg(feature = "parquet")]
+    if let Some(path) = args.parquet {
+        let f = get_output_file_truncated(&path)?;
+        crate::parquet::write_parquet(f, &records)?;
+    }
+
+    #[cfg(feature = "heatmap")]
+    if let Some(path) = args.heatmap {
+        match crate::heatmap::Heatmap::from_records(&records) {
+            Some(heatmap) => heatmap.write_svg(get_output_file_truncated(&path)?)?,
+            None if !args.silent => println!("warning: no requests to render a heatmap of"),
+            None => {}
+        }
+    }
+
+    let mut stats = Stats::from_responses(&res, &failures);
+
+    if let Some(stats) = &mut stats {
+        stats.body_encoding = runs
+            .iter()
+            .find_map(|r| r.client.body_encoding())
+            .map(String::from);
+        stats.token_refreshes = token.as_ref().map(|t| t.refreshes());
+        stats.connection_reuse = connection_reuse;
+        stats.tls_handshakes = handshakes;
+        stats.clock_resolution = Some(clock_resolution);
+        stats.multiplexing = multiplexing;
+        stats.sticky = runs.iter().find_map(|r| {
+            let indices = res.iter().map(|r| r.index);
+            let indices = indices.chain(failures.iter().map(|f| f.index));
+            Some(r.client.sticky()?.distribution(r.client.urls(), indices))
+        });
+        stats.transfer = transfer;
+        stats.upload = upload;
+        stats.phases = args.phases.then(|| Phases::from_responses(&res));
+        stats.delivery = args.ttlb.then(|| Delivery::from_responses(&res)).flatten();
+        stats.target_rate = target_rate;
+        stats.samples = args
+            .summary_samples
+            .then(|| res.iter().map(|r| r.took.as_nanos() as u64).collect());
+        stats.compressed = args
+            .no_decompress
+            .then(|| res.iter().filter(|r| r.content_encoding.is_some()).count());
+    }
+
+    if let (true, Some(stats)) = (args.distribution, &mut stats) {
+        let times: Vec<_> = res.iter().map(|r| r.took).collect();
+        stats.distribution = Distribution::from_times(&times);
+        if stats.distribution.is_none() && !args.silent {
+            println!(
+                "warning: the latency distribution requires at least {} samples with varying \
+                latencies and will be omitted",
+                Distribution::MIN_SAMPLES
+            );
+        }
+    }
+
+    if let (Some(header), Some(stats)) = (&args.server_time_header, &mut stats) {
+        stats.server_time = ServerTime::from_responses(&res, header);
+        if stats.server_time.is_none() && !args.silent {
+            println!("warning: no response reported a valid server time in '{header}'");
+        }
+    }
+
+    if let (Some(pct), Some(stats)) = (args.trim, &mut stats) {
+        let mut times: Vec<_> = res.iter().map(|r| r.took).collect();
+        times.sort();
+        stats.trimmed = Trimmed::from_times(&times, pct);
+    }
+
+    if let (true, Some(stats)) = (args.ci, &mut stats) {
+        let mut times: Vec<_> = res.iter().map(|r| r.took).collect();
+        times.sort();
+        stats.confidence = Confidence::bootstrap(&times, args.ci_iterations, seed);
+    }
+
+    if args.csv {
+        write_csv(io::stdout(), &records, args.ordered)?;
+    } else if !args.silent && !per_target {
+        match &stats {
+            Some(stats) if args.markdown => stats.print_markdown(args.unit),
+            Some(stats) => stats.print(min_samples, args.unit),
+            None => print_no_results(&failures),
+        }
+    }
+
+    if let (Some(path), Some(stats)) = (args.summary_json, &stats) {
+        let f = get_output_file_truncated(&path)?;
+        serde_json::to_writer_pretty(f, stats)?;
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = &args.sqlite {
+        let url = runs
+            .iter()
+            .flat_map(|r| r.client.urls())
+            .map(|u| u.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        let command = std::env::args().collect::<Vec<_>>().join(" ");
+        let run = crate::sqlite::RunInfo {
+            started_at,
+            url: &url,
+            command: &command,
+        };
+        crate::sqlite::write_sqlite(path, &run, &records, stats.as_ref())?;
+    }
+
+    let slo_breached =
+        !args.slo.is_empty() && is_slo_breached(&args.slo, stats.as_ref(), args.silent);
+
+    let mut regressed = false;
+    if let (Some(baseline), Some(stats)) = (baseline, &stats) {
+        let deltas = compare::compare(&baseline, stats);
+
+        if !args.csv && !args.silent {
+            println!();
+            compare::print_deltas(&deltas, args.regression_threshold);
+
+            if let Some(samples) = baseline.samples() {
+                let times: Vec<_> = res.iter().map(|r| r.took).collect();
+                match MannWhitney::test(&samples, &times) {
+                    Some(t) => t.print(args.alpha),
+                    None => print_untestable(),
+                }
+            }
+        }
+
+        regressed = is_regression(&deltas, args.regression_threshold, args.silent);
+    }
+
+    if slo_breached {
+        return Ok(Exit::Slo.into());
+    }
+    if regressed {
+        return Ok(Exit::Regression.into());
+    }
+
+    let golden_mismatches = res.iter().filter(|r| r.golden_match == Some(false)).count();
+    if golden_mismatches > 0 {
+        if !args.silent {
+            eprintln!(
+                "error: {golden_mismatches} of {} response bodies did not match the golden file",
+                res.len()
+            );
+
+            if args.golden_diff {
+                let mismatch = runs
+                    .iter()
+                    .find_map(|r| Some((r.client.golden()?, r.client.golden_mismatch()?)));
+                if let Some((expected, actual)) = mismatch {
+                    println!();
+                    diff::print_diff(
+                        &String::from_utf8_lossy(expected),
+                        &String::from_utf8_lossy(actual),
+                    );
+                }
+            }
+        }
+        return Ok(Exit::Assertion.into());
+    }
+
+    if let Some(w) = aborted {
+        if !args.silent {
+            eprintln!(
+                "error: aborted after {} requests, because {} of the last {} requests failed \
+                ({:.2}% exceeds {}%)",
+                w.completed,
+                w.failed,
+                w.size,
+                w.rate(),
+                args.max_error_rate.unwrap_or_default(),
+            );
+        }
+        return Ok(Exit::ErrorRate.into());
+    }
+
+    if args.check {
+        let failed = res.iter().filter(|r| !r.is_success()).count() + failures.len();
+        if failed > 0 {
+            if !args.silent {
+                eprintln!(
+                    "error: {failed} of {} requests did not succeed",
+                    res.len() + failures.len()
+                );
+            }
+            return Ok(Exit::ErrorRate.into());
+        }
+    }
+
+    if args.check_2xx {
+        let non_2xx = res
+            .iter()
+            .filter(|r| r.injected || !r.status.is_success())
+            .count()
+            + failures.len();
+        if non_2xx > 0 {
+            if !args.silent {
+                eprintln!(
+                    "error: {non_2xx} of {} requests were not answered with 2xx",
+                    res.len() + failures.len()
+                );
+            }
+            return Ok(Exit::ErrorRate.into());
+        }
+    }
+
+    Ok(Exit::Success.into())
+}
+
+fn compare_summaries(
+    baseline: &str,
+    current: &str,
+    threshold: f64,
+    alpha: f64,
+) -> Result<ExitCode> {
+    let read = |path| {
+        compare::read_stats(path).map_err(|err| anyhow::anyhow!("invalid summary '{path}': {err}"))
+    };
+    let (baseline, current) = (read(baseline)?, read(current)?);
+    let deltas = compare::compare(&baseline, &current);
+    compare::print_deltas(&deltas, threshold);
+
+    if let (Some(a), Some(b)) = (baseline.samples(), current.samples()) {
+        match MannWhitney::test(&a, &b) {
+            Some(t) => t.print(alpha),
+            None => print_untestable(),
+        }
+    }
+
+    if is_regression(&deltas, threshold, false) {
+        return Ok(Exit::Regression.into());
+    }
+    Ok(Exit::Success.into())
+}
+
+fn aggregate_summaries(paths: &[String]) -> Result<ExitCode> {
+    let stats = paths
+        .iter()
+        .map(|path| {
+            compare::read_stats(path)
+                .map_err(|err| anyhow::anyhow!("invalid summary '{path}': {err}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    aggregate::print_spreads(&aggregate::aggregate(&stats), stats.len());
+    Ok(Exit::Success.into())
+}
+
+fn print_untestable() {
+    println!(
+        "\nwarning: the significance cannot be tested with less than {} samples per run or \
+        equal latencies",
+        MannWhitney::MIN_SAMPLES
+    );
+}
+
+/// Returns true and prints an error, unless `silent` is set, if any of
+/// the metrics regressed by more than `threshold` percent.
+fn is_regression(deltas: &[compare::Delta], threshold: f64, silent: bool) -> bool {
+    let regressed = deltas.iter().any(|d| d.is_regression(threshold));
+    if regressed && !silent {
+        eprintln!("error: latency regressed by more than {threshold}% compared to the baseline");
+    }
+    regressed
+}
+
+/// Returns whether any SLO has been exceeded; a run without any latency,
+/// i.e. in which all requests failed, breaches all SLOs.
+fn is_slo_breached(slos: &[Slo], stats: Option<&Stats>, silent: bool) -> bool {
+    let Some(stats) = stats else {
+        if !silent {
+            eprintln!("error: no request has been completed to check the SLOs against");
+        }
+        return true;
+    };
+
+    let breached: Vec<_> = slos.iter().filter(|slo| slo.is_breached(stats)).collect();
+    if !silent {
+        for slo in &breached {
+            eprintln!(
+                "error: the {} latency of {:.4} exceeds the SLO of {:.4}",
+                slo.metric,
+                format_duration(slo.value(stats)),
+                format_duration(slo.limit)
+            );
+        }
+    }
+    !breached.is_empty()
+}
+
+/// Returns the command line arguments preceded by the flags read from
+/// the files passed via `config`, if any.
+fn args_with_config() -> Result<Vec<OsString>> {
+    let mut args: Vec<_> = std::env::args_os().collect();
+
+    let mut paths = vec![];
+    let mut rest = args.iter().skip(1);
+    while let Some(arg) = rest.next() {
+        match arg.to_str() {
+            Some("--") => break,
+            Some("--config") => paths.extend(rest.next().cloned()),
+            Some(arg) => {
+                if let Some(path) = arg.strip_prefix("--config=") {
+                    paths.push(path.into());
+                }
+            }
+            None => {}
+        }
+    }
+    if paths.is_empty() {
+        return Ok(args);
+    }
+
+    // The flags which can be passed multiple times are appended across
+    // the config files instead of being replaced.
+    let command = Args::command();
+    let lists: Vec<_> = command
+        .get_arguments()
+        .filter(|a| matches!(a.get_action(), ArgAction::Append))
+        .filter_map(|a| a.get_long())
+        .collect();
+    let config = config::read_layers(&paths, &lists)?;
+    args.splice(
+        1..1,
+        config::to_args(&config).into_iter().map(OsString::from),
+    );
+    Ok(args)
+}
+
+fn parse_method_body(v: &str) -> Result<(String, String)> {
+    let (method, body) = v
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("expected the format 'METHOD=BODY'"))?;
+    Ok((method.trim().to_string(), body.to_string()))
+}
+
+fn parse_percentage(v: &str) -> Result<f64> {
+    let pct: f64 = v.parse()?;
+    if !(0f64..=100f64).contains(&pct) {
+        anyhow::bail!("the percentage must be between 0 and 100");
+    }
+    Ok(pct)
+}
+
+fn parse_rate(v: &str) -> Result<f64> {
+    let rate: f64 = v.parse()?;
+    if !(rate.is_finite() && rate > 0f64) {
+        anyhow::bail!("the rate must be greater than 0");
+    }
+    Ok(rate)
+}
+
+fn parse_stack_size(v: &str) -> Result<usize> {
+    let size: usize = v.parse()?;
+    if size < MIN_STACK_SIZE {
+        anyhow::bail!("the stack size must be at least {MIN_STACK_SIZE} bytes");
+    }
+    Ok(size)
+}
+
+fn parse_trim_percentage(v: &str) -> Result<f64> {
+    let pct: f64 = v.parse()?;
+    if !(0f64..50f64).contains(&pct) {
+        anyhow::bail!("the percentage must be at least 0 and less than 50");
+    }
+    Ok(pct)
+}
+
+fn read_body_from_file(file_path: &str) -> Result<Vec<u8>> {
+    let mut f = File::open(file_path)?;
+    let mut buf = vec![];
+    f.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_bodies_per_line(file_path: &str) -> Result<Vec<Vec<u8>>> {
+    let bodies: Vec<_> = read_body_from_file(file_path)?
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .map(<[u8]>::to_vec)
+        .collect();
+
+    if bodies.is_empty() {
+        anyhow::bail!("the body file does not contain any lines");
+    }
+
+    Ok(bodies)
+}
+
+fn print_no_results(failures: &[Failure]) {
+    println!("no result values");
+    if !failures.is_empty() {
+        println!("{} of the requests failed:", failures.len());
+        stats::print_error_reasons(&ErrorReason::top(failures));
+    }
+}
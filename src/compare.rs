@@ -0,0 +1,287 @@
+use crate::{duration::format_duration, stats::Stats};
+use anyhow::Result;
+use std::{fs::File, io::BufReader, path::Path, str::FromStr, time::Duration};
+
+/// The difference of a single metric between a baseline and a
+/// current run.
+pub struct Delta {
+    pub name: &'static str,
+    pub baseline: Duration,
+    pub current: Duration,
+}
+
+impl Delta {
+    /// Returns the relative change of the current value against the
+    /// baseline value in percent.
+    pub fn percent(&self) -> f64 {
+        let baseline = self.baseline.as_nanos() as f64;
+        if baseline == 0f64 {
+            return 0f64;
+        }
+
+        (self.current.as_nanos() as f64 - baseline) / baseline * 100f64
+    }
+
+    /// Returns true if the current value exceeds the baseline value
+    /// by more than `threshold` percent.
+    pub fn is_regression(&self, threshold: f64) -> bool {
+        self.percent() > threshold
+    }
+}
+
+/// Reads a latency metric from the stats of a run.
+type Metric = fn(&Stats) -> Duration;
+
+/// The metrics an SLO can be set for, by their name.
+const SLO_METRICS: &[(&str, Metric)] = &[
+    ("avg", |s| s.avg),
+    ("median", |s| s.median),
+    ("p90", |s| s.p90),
+    ("p95", |s| s.p95),
+    ("p99", |s| s.p99),
+    ("max", |s| s.max),
+];
+
+/// A service level objective, i.e. a latency a metric of a run must not
+/// exceed; parsed from 'metric=duration', e.g. 'p95=200ms'.
+#[derive(Debug, Clone, Copy)]
+pub struct Slo {
+    pub metric: &'static str,
+    pub limit: Duration,
+    value: Metric,
+}
+
+impl Slo {
+    /// Returns the value of the metric of the run.
+    pub fn value(&self, stats: &Stats) -> Duration {
+        (self.value)(stats)
+    }
+
+    pub fn is_breached(&self, stats: &Stats) -> bool {
+        self.value(stats) > self.limit
+    }
+}
+
+impl FromStr for Slo {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (metric, limit) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("expected 'metric=duration', e.g. 'p95=200ms'"))?;
+        let &(metric, value) = SLO_METRICS
+            .iter()
+            .find(|(name, _)| *name == metric)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unsupported metric '{metric}'; expected one of 'avg', 'median', 'p90', \
+                    'p95', 'p99' and 'max'"
+                )
+            })?;
+        Ok(Self {
+            metric,
+            limit: humantime::parse_duration(limit)?,
+            value,
+        })
+    }
+}
+
+/// The result of a two-sided Mann-Whitney U test, which tests whether
+/// the latencies of one run tend to be larger than those of the other
+/// without assuming a distribution of the latencies.
+#[derive(Debug, Clone, Copy)]
+pub struct MannWhitney {
+    /// The U statistic of the first sample set.
+    pub u: f64,
+    /// The standard score of `u` under the normal approximation.
+    pub z: f64,
+    pub p_value: f64,
+}
+
+impl MannWhitney {
+    /// The minimum size of each sample set for which the normal
+    /// approximation is reasonably accurate.
+    pub const MIN_SAMPLES: usize = 8;
+
+    /// Runs the test on the given sample sets. Returns `None` if either
+    /// set has less than [`Self::MIN_SAMPLES`] samples or all samples are
+    /// equal.
+    pub fn test(a: &[Duration], b: &[Duration]) -> Option<Self> {
+        if a.len() < Self::MIN_SAMPLES || b.len() < Self::MIN_SAMPLES {
+            return None;
+        }
+
+        let mut all: Vec<_> = a
+            .iter()
+            .map(|d| (*d, true))
+            .chain(b.iter().map(|d| (*d, false)))
+            .collect();
+        all.sort_by_key(|(d, _)| *d);
+
+        // Assigns the average rank to tied samples and collects the
+        // tie correction term of the variance.
+        let mut rank_sum_a = 0f64;
+        let mut ties = 0f64;
+        let mut i = 0;
+        while i < all.len() {
+            let j = i + all[i..].iter().take_while(|(d, _)| *d == all[i].0).count();
+            let rank = (i + j + 1) as f64 / 2f64;
+            rank_sum_a += rank * all[i..j].iter().filter(|(_, is_a)| *is_a).count() as f64;
+            let t = (j - i) as f64;
+            ties += t.powi(3) - t;
+            i = j;
+        }
+
+        let (n1, n2) = (a.len() as f64, b.len() as f64);
+        let n = n1 + n2;
+        let u = rank_sum_a - n1 * (n1 + 1f64) / 2f64;
+        let mean = n1 * n2 / 2f64;
+        let var = n1 * n2 / 12f64 * ((n + 1f64) - ties / (n * (n - 1f64)));
+        if var <= 0f64 {
+            return None;
+        }
+
+        let z = (u - mean) / var.sqrt();
+        let p_value = (2f64 * (1f64 - normal_cdf(z.abs()))).clamp(0f64, 1f64);
+        Some(Self { u, z, p_value })
+    }
+
+    pub fn is_significant(&self, alpha: f64) -> bool {
+        self.p_value < alpha
+    }
+
+    pub fn print(&self, alpha: f64) {
+        println!(
+            "\nMann-Whitney U = {}, z = {:.2}: p = {:.4}; the difference is {}significant at alpha = {alpha}",
+            self.u,
+            self.z,
+            self.p_value,
+            if self.is_significant(alpha) {
+                ""
+            } else {
+                "not "
+            },
+        );
+    }
+}
+
+/// The cumulative distribution function of the standard normal
+/// distribution.
+fn normal_cdf(z: f64) -> f64 {
+    0.5 * (1f64 + erf(z / std::f64::consts::SQRT_2))
+}
+
+/// Approximates the error function with a maximum error of 1.5e-7
+/// (Abramowitz and Stegun, formula 7.1.26).
+fn erf(x: f64) -> f64 {
+    let t = 1f64 / (1f64 + 0.3275911 * x.abs());
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    (1f64 - poly * (-x * x).exp()).copysign(x)
+}
+
+pub fn read_stats(path: impl AsRef<Path>) -> Result<Stats> {
+    let f = File::open(path)?;
+    let stats = serde_json::from_reader(BufReader::new(f))?;
+    Ok(stats)
+}
+
+pub fn compare(baseline: &Stats, current: &Stats) -> Vec<Delta> {
+    let delta = |name, baseline, current| Delta {
+        name,
+        baseline,
+        current,
+    };
+
+    vec![
+        delta("Average", baseline.avg, current.avg),
+        delta("Median", baseline.median, current.median),
+        delta("90th %ile.", baseline.p90, current.p90),
+        delta("95th %ile.", baseline.p95, current.p95),
+        delta("99th %ile.", baseline.p99, current.p99),
+    ]
+}
+
+pub fn print_deltas(deltas: &[Delta], threshold: f64) {
+    println!(
+        "{:<12}{:>14}{:>14}{:>10}",
+        "Metric", "Baseline", "Current", "Delta"
+    );
+
+    for d in deltas {
+        let marker = if d.is_regression(threshold) {
+            "  !"
+        } else {
+            ""
+        };
+        println!(
+            "{:<12}{:>14}{:>14}{:>+9.2}%{marker}",
+            format!("{}:", d.name),
+            format!("{:.4}", format_duration(d.baseline)),
+            format!("{:.4}", format_duration(d.current)),
+            d.percent(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn delta_percent() {
+        let d = Delta {
+            name: "",
+            baseline: Duration::from_millis(100),
+            current: Duration::from_millis(125),
+        };
+        assert_eq!(25f64, d.percent());
+        assert!(d.is_regression(10f64));
+        assert!(!d.is_regression(25f64));
+
+        let d = Delta {
+            name: "",
+            baseline: Duration::from_millis(100),
+            current: Duration::from_millis(50),
+        };
+        assert_eq!(-50f64, d.percent());
+        assert!(!d.is_regression(0f64));
+
+        let d = Delta {
+            name: "",
+            baseline: Duration::ZERO,
+            current: Duration::from_millis(50),
+        };
+        assert_eq!(0f64, d.percent());
+    }
+
+    #[test]
+    fn slo() {
+        let slo: Slo = "p95=200ms".parse().unwrap();
+        assert_eq!(("p95", Duration::from_millis(200)), (slo.metric, slo.limit));
+
+        assert!("p95".parse::<Slo>().is_err());
+        assert!("p50=1s".parse::<Slo>().is_err());
+        assert!("max=fast".parse::<Slo>().is_err());
+    }
+
+    #[test]
+    fn mann_whitney() {
+        let a: Vec<_> = (1..=20).map(Duration::from_millis).collect();
+        let b: Vec<_> = (1..=20).rev().map(Duration::from_millis).collect();
+        let t = MannWhitney::test(&a, &b).unwrap();
+        assert_eq!(200f64, t.u);
+        assert!((t.p_value - 1f64).abs() < 1e-6 && !t.is_significant(0.05));
+
+        let b: Vec<_> = (15..=34).map(Duration::from_millis).collect();
+        let t = MannWhitney::test(&a, &b).unwrap();
+        // 5 + 4 + 3 + 2 + 1 pairs where a is larger plus 6 halved ties.
+        assert_eq!(18f64, t.u);
+        assert!((t.z + 4.9245).abs() < 1e-4);
+        assert!(t.p_value < 1e-5 && t.is_significant(0.05));
+
+        assert!(MannWhitney::test(&a[..4], &b).is_none());
+        assert!(MannWhitney::test(&[Duration::ZERO; 10], &[Duration::ZERO; 10]).is_none());
+    }
+}
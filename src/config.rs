@@ -0,0 +1,172 @@
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::{fs::File, io::BufReader, path::Path};
+
+/// The flags of a config file by their long name, e.g. `"parallel": 8`
+/// for `--parallel 8`.
+pub type Config = Map<String, Value>;
+
+/// Reads the config files in the given order and merges them into a
+/// single config, so that later files override earlier ones. `lists` are
+/// the long names of the flags which can be passed multiple times.
+pub fn read_layers<P: AsRef<Path>>(paths: &[P], lists: &[&str]) -> Result<Config> {
+    let mut config = Config::new();
+    for path in paths {
+        let path = path.as_ref();
+        let layer = read(path, lists)
+            .map_err(|err| anyhow::anyhow!("invalid config '{}': {err}", path.display()))?;
+        merge(&mut config, layer, lists);
+    }
+    Ok(config)
+}
+
+fn read(path: &Path, lists: &[&str]) -> Result<Config> {
+    let f = File::open(path)?;
+    let layer: Config = serde_json::from_reader(BufReader::new(f))?;
+
+    let mut normalized = Config::new();
+    for (key, value) in layer {
+        let key = key.replace('_', "-");
+        match &value {
+            _ if key == "config" => anyhow::bail!("config files cannot include other config files"),
+            Value::Object(_) => anyhow::bail!("the value of '{key}' must not be an object"),
+            Value::Array(values) if values.iter().any(|v| v.is_array() || v.is_object()) => {
+                anyhow::bail!("the values of '{key}' must be strings, numbers or booleans")
+            }
+            Value::Array(_) if !lists.contains(&key.as_str()) => {
+                anyhow::bail!("'{key}' can only be passed once, so its value must not be a list")
+            }
+            _ => {}
+        }
+        normalized.insert(key, value);
+    }
+    Ok(normalized)
+}
+
+/// Merges the layer into the config. The values of the `lists` flags
+/// are appended to the ones of the config, so that e.g. headers of a base
+/// config are kept and only extended by an override, while the values
+/// of all other flags are replaced. A null value resets the flag,
+/// including a list, to its default.
+pub fn merge(config: &mut Config, layer: Config, lists: &[&str]) {
+    for (key, value) in layer {
+        let list = lists.contains(&key.as_str());
+        let more = match value {
+            Value::Null => {
+                config.remove(&key);
+                continue;
+            }
+            Value::Array(values) if list => values,
+            value if list => vec![value],
+            value => {
+                config.insert(key, value);
+                continue;
+            }
+        };
+        match config.get_mut(&key) {
+            Some(Value::Array(values)) => values.extend(more),
+            Some(value) => {
+                let first = value.take();
+                *value = Value::Array(std::iter::once(first).chain(more).collect());
+            }
+            None => {
+                config.insert(key, Value::Array(more));
+            }
+        }
+    }
+}
+
+/// Converts the config into command line arguments, which are passed
+/// before the actual ones, so that flags passed via the command line
+/// override the config and lists are appended to the ones of the config.
+pub fn to_args(config: &Config) -> Vec<String> {
+    let mut args = vec![];
+    for (key, value) in config {
+        let values = match value {
+            Value::Array(values) => values.as_slice(),
+            value => std::slice::from_ref(value),
+        };
+        for value in values {
+            match value {
+                Value::Bool(true) => args.push(format!("--{key}")),
+                Value::Bool(false) | Value::Null => {}
+                Value::String(s) => args.push(format!("--{key}={s}")),
+                value => args.push(format!("--{key}={value}")),
+            }
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util;
+    use serde_json::json;
+
+    const LISTS: &[&str] = &["header", "method-cycle", "slo"];
+
+    fn config(v: Value) -> Config {
+        match v {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn layers() {
+        let mut merged = config(json!({
+            "parallel": 4,
+            "header": ["Authorization: Bearer base"],
+            "drain-body": true,
+            "timeout": "1s",
+            "slo": "p95=1s",
+        }));
+        merge(
+            &mut merged,
+            config(json!({
+                "parallel": 16,
+                "header": "X-Env: staging",
+                "drain-body": false,
+                "timeout": null,
+                "count": 1000,
+                "slo": "p99=2s",
+            })),
+            LISTS,
+        );
+
+        assert_eq!(
+            vec![
+                "--parallel=16",
+                "--header=Authorization: Bearer base",
+                "--header=X-Env: staging",
+                "--slo=p95=1s",
+                "--slo=p99=2s",
+                "--count=1000",
+            ],
+            to_args(&merged)
+        );
+    }
+
+    #[test]
+    fn read_layers_from_files() {
+        let dir = test_util::temp_dir("config");
+        let base = dir.join("base.json");
+        let env = dir.join("env.json");
+        std::fs::write(&base, r#"{ "drain_body": true, "method-cycle": ["GET"] }"#).unwrap();
+        std::fs::write(&env, r#"{ "method_cycle": ["POST"] }"#).unwrap();
+
+        let merged = read_layers(&[&base, &env], LISTS).unwrap();
+        assert_eq!(
+            vec!["--drain-body", "--method-cycle=GET", "--method-cycle=POST"],
+            to_args(&merged)
+        );
+
+        std::fs::write(&env, r#"{ "config": "base.json" }"#).unwrap();
+        assert!(read_layers(&[&env], LISTS).is_err());
+        std::fs::write(&env, r#"{ "header": { "a": "b" } }"#).unwrap();
+        assert!(read_layers(&[&env], LISTS).is_err());
+        std::fs::write(&env, r#"{ "parallel": [1, 2] }"#).unwrap();
+        assert!(read_layers(&[&env], LISTS).is_err());
+    }
+}
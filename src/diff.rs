@@ -0,0 +1,90 @@
+/// The maximum amount of lines per side for which a diff is computed.
+const MAX_LINES: usize = 1_000;
+
+#[derive(Debug, PartialEq)]
+pub enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Computes a line based diff between `expected` and `actual` using the
+/// longest common subsequence. Returns `None` if either side has more
+/// than [`MAX_LINES`] lines.
+pub fn diff<'a>(expected: &'a str, actual: &'a str) -> Option<Vec<Line<'a>>> {
+    let a: Vec<_> = expected.lines().collect();
+    let b: Vec<_> = actual.lines().collect();
+
+    if a.len() > MAX_LINES || b.len() > MAX_LINES {
+        return None;
+    }
+
+    // lcs[i][j] holds the length of the longest common subsequence
+    // of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            lines.push(Line::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(Line::Removed(a[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(b[j]));
+            j += 1;
+        }
+    }
+    lines.extend(a[i..].iter().map(|l| Line::Removed(l)));
+    lines.extend(b[j..].iter().map(|l| Line::Added(l)));
+
+    Some(lines)
+}
+
+pub fn print_diff(expected: &str, actual: &str) {
+    let Some(lines) = diff(expected, actual) else {
+        println!("(the bodies are too large to be diffed)");
+        return;
+    };
+
+    for line in lines {
+        match line {
+            Line::Same(l) => println!("  {l}"),
+            Line::Removed(l) => println!("- {l}"),
+            Line::Added(l) => println!("+ {l}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn line_diff() {
+        let lines = diff("a\nb\nc\n", "a\nc\nd").unwrap();
+        assert_eq!(
+            vec![
+                Line::Same("a"),
+                Line::Removed("b"),
+                Line::Same("c"),
+                Line::Added("d"),
+            ],
+            lines
+        );
+
+        assert_eq!(vec![Line::Same("a")], diff("a", "a").unwrap());
+    }
+}
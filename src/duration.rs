@@ -1,47 +1,145 @@
 use humantime::parse_duration;
 use rand::Rng;
+use rand_distr::{Distribution, Exp, LogNormal};
 use std::{fmt, str::FromStr, time::Duration};
 
-pub struct DurationRange(Duration, Duration);
+/// A source of random durations.
+///
+/// Parsed from either a single duration (e.g. '10ms'), a uniform range
+/// (e.g. '10ms..20ms'), an exponential distribution with the given mean
+/// (e.g. 'exp:500ms') or a log-normal distribution with the given median
+/// and shape (e.g. 'lognormal:500ms:1.5').
+#[derive(Debug, Clone)]
+pub enum DurationRange {
+    Uniform(Duration, Duration),
+    Exp(Exp<f64>),
+    LogNormal(LogNormal<f64>),
+}
 
 impl DurationRange {
     pub fn get_random(&self) -> Duration {
-        if self.is_flat() {
-            return self.0;
-        }
+        let secs = match self {
+            Self::Uniform(start, end) if start == end => return *start,
+            Self::Uniform(start, end) => return rand::thread_rng().gen_range(*start..*end),
+            Self::Exp(d) => d.sample(&mut rand::thread_rng()),
+            Self::LogNormal(d) => d.sample(&mut rand::thread_rng()),
+        };
 
-        rand::thread_rng().gen_range(self.0..self.1)
+        Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX)
     }
 
     pub fn is_flat(&self) -> bool {
-        self.0 == self.1
+        matches!(self, Self::Uniform(start, end) if start == end)
     }
 
+    /// Returns the lower bound of the durations.
     pub fn start(&self) -> &Duration {
-        &self.0
+        match self {
+            Self::Uniform(start, _) => start,
+            _ => &Duration::ZERO,
+        }
     }
 }
 
 impl FromStr for DurationRange {
-    type Err = humantime::DurationError;
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(mean) = s.strip_prefix("exp:") {
+            let mean = parse_duration(mean)?.as_secs_f64();
+            if mean == 0f64 {
+                anyhow::bail!("the mean of the exponential distribution must not be zero");
+            }
+            return Ok(Self::Exp(Exp::new(1f64 / mean)?));
+        }
+
+        if let Some(params) = s.strip_prefix("lognormal:") {
+            let (median, sigma) = params
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("expected the format 'lognormal:median:sigma'"))?;
+            let median = parse_duration(median)?.as_secs_f64();
+            if median == 0f64 {
+                anyhow::bail!("the median of the log-normal distribution must not be zero");
+            }
+            let sigma: f64 = sigma.parse()?;
+            if sigma.is_nan() || sigma < 0f64 {
+                anyhow::bail!("the shape of the log-normal distribution must not be negative");
+            }
+            return Ok(Self::LogNormal(LogNormal::new(median.ln(), sigma)?));
+        }
+
         if let Some((start, end)) = s.split_once("..") {
             let start = parse_duration(start)?;
             let end = parse_duration(end)?;
-            return Ok(Self(start, end));
+            if start > end {
+                anyhow::bail!("the start of the range must not exceed its end");
+            }
+            return Ok(Self::Uniform(start, end));
         }
 
         let d = parse_duration(s)?;
-        Ok(Self(d, d))
+        Ok(Self::Uniform(d, d))
+    }
+}
+
+/// A unit all durations are formatted in, instead of the unit picked
+/// for each value, so that printed values line up for comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationUnit {
+    Nanos,
+    Micros,
+    Millis,
+    Secs,
+}
+
+impl DurationUnit {
+    fn nanos(self) -> f64 {
+        match self {
+            Self::Nanos => 1f64,
+            Self::Micros => 1_000f64,
+            Self::Millis => 1_000_000f64,
+            Self::Secs => 1_000_000_000f64,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Nanos => "ns",
+            Self::Micros => "µs",
+            Self::Millis => "ms",
+            Self::Secs => "s",
+        }
     }
 }
 
-pub struct ShortDurationFormatter(Duration);
+impl FromStr for DurationUnit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ns" => Ok(Self::Nanos),
+            "us" | "µs" => Ok(Self::Micros),
+            "ms" => Ok(Self::Millis),
+            "s" => Ok(Self::Secs),
+            _ => anyhow::bail!("unsupported unit: '{s}'; expected 'ns', 'us', 'ms' or 's'"),
+        }
+    }
+}
+
+pub struct ShortDurationFormatter(Duration, Option<DurationUnit>);
 
 impl ShortDurationFormatter {
+    /// Formats the duration in the given unit instead of the one picked
+    /// for its value, if any.
+    pub fn in_unit(self, unit: Option<DurationUnit>) -> Self {
+        Self(self.0, unit)
+    }
+
     fn unitify(&self) -> (&'static str, f64) {
         let nanos = self.0.as_nanos();
+        if let Some(unit) = self.1 {
+            return (unit.symbol(), nanos as f64 / unit.nanos());
+        }
         match self.0.as_nanos() {
             0..=999 => ("ns", nanos as f64),
             1_000..=999_999 => ("µs", nanos as f64 / 1_000f64),
@@ -55,7 +153,7 @@ impl ShortDurationFormatter {
 
 impl From<Duration> for ShortDurationFormatter {
     fn from(value: Duration) -> Self {
-        Self(value)
+        Self(value, None)
     }
 }
 
@@ -90,10 +188,122 @@ pub fn format_duration(d: Duration) -> ShortDurationFormatter {
     d.into()
 }
 
+/// Parses a duration reported by a server in a response header, e.g.
+/// '12.5ms' or '0.003s'. Values without a unit are taken as
+/// milliseconds, as are the 'dur' parameters of a `Server-Timing`
+/// header, e.g. 'app;dur=12.5'. Returns `None` if the value cannot be
+/// parsed.
+pub fn parse_header_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let value = match value.split_once("dur=") {
+        Some((_, dur)) => dur.split([';', ',']).next()?.trim(),
+        None => value,
+    };
+
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let secs = match unit.trim() {
+        "ns" => number / 1e9,
+        "us" | "µs" => number / 1e6,
+        "" | "ms" => number / 1e3,
+        "s" => number,
+        _ => return None,
+    };
+
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+/// (De)serializes a [`Duration`] as an integer amount of nanoseconds.
+pub mod nanos {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(v: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_u64(v.as_nanos() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        u64::deserialize(d).map(Duration::from_nanos)
+    }
+}
+
+/// (De)serializes an optional [`Duration`] as an integer amount of
+/// nanoseconds or null.
+pub mod nanos_option {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(v: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+        match v {
+            Some(v) => s.serialize_some(&(v.as_nanos() as u64)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(d)?.map(Duration::from_nanos))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn parse_ranges() {
+        let r: DurationRange = "10ms".parse().unwrap();
+        assert!(r.is_flat());
+        assert_eq!(Duration::from_millis(10), r.get_random());
+
+        let r: DurationRange = "10ms..20ms".parse().unwrap();
+        assert!(!r.is_flat());
+        assert_eq!(&Duration::from_millis(10), r.start());
+        let d = r.get_random();
+        assert!(d >= Duration::from_millis(10) && d < Duration::from_millis(20));
+
+        assert!("5s..1s".parse::<DurationRange>().is_err());
+    }
+
+    #[test]
+    fn parse_distributions() {
+        let mean = |r: DurationRange| {
+            (0..10_000)
+                .map(|_| r.get_random().as_secs_f64())
+                .sum::<f64>()
+                / 10_000f64
+        };
+
+        let r: DurationRange = "exp:500ms".parse().unwrap();
+        assert!(!r.is_flat());
+        assert_eq!(&Duration::ZERO, r.start());
+        assert!((0.45..0.55).contains(&mean(r)));
+
+        // The mean of a log-normal distribution is exp(mu + sigma^2 / 2).
+        let r: DurationRange = "lognormal:500ms:0.5".parse().unwrap();
+        assert!((0.53..0.60).contains(&mean(r)));
+
+        assert!("exp:0ms".parse::<DurationRange>().is_err());
+        assert!("exp:fast".parse::<DurationRange>().is_err());
+        assert!("lognormal:500ms".parse::<DurationRange>().is_err());
+        assert!("lognormal:500ms:-1".parse::<DurationRange>().is_err());
+    }
+
+    #[test]
+    fn header_durations() {
+        let ms = |v: f64| Some(Duration::from_secs_f64(v / 1e3));
+        assert_eq!(ms(12.5), parse_header_duration("12.5ms"));
+        assert_eq!(ms(12.5), parse_header_duration(" 12.5 "));
+        assert_eq!(ms(3f64), parse_header_duration("0.003s"));
+        assert_eq!(ms(0.25), parse_header_duration("250us"));
+        assert_eq!(ms(7f64), parse_header_duration("db;dur=7, app;dur=12"));
+        assert_eq!(None, parse_header_duration("fast"));
+        assert_eq!(None, parse_header_duration("12 minutes"));
+    }
+
     #[test]
     fn duration_formatter() {
         let d = Duration::from_nanos(123);
@@ -127,5 +337,16 @@ mod test {
         let d = Duration::from_nanos(23_456_789_012);
         let f = format!("{:>8.2}", format_duration(d));
         assert_eq!("   23.46s", f);
+
+        let ms = Some("ms".parse().unwrap());
+        let d = Duration::from_nanos(123_456);
+        let f = format!("{:.3}", format_duration(d).in_unit(ms));
+        assert_eq!("0.123ms", f);
+
+        let d = Duration::from_secs(90);
+        let f = format!("{}", format_duration(d).in_unit(ms));
+        assert_eq!("90000ms", f);
+
+        assert!("m".parse::<DurationUnit>().is_err());
     }
 }
@@ -0,0 +1,226 @@
+use anyhow::Result;
+use rand::{distributions::Alphanumeric, rngs::StdRng, Rng, SeedableRng};
+use serde_json::Value;
+use std::fmt::Write;
+
+/// A JSON request body generated per request from a spec.
+///
+/// The spec is a JSON document in which string values starting with '$'
+/// are replaced with generated values:
+/// - `$int:MIN:MAX`: a random integer between MIN and MAX, inclusive
+/// - `$float:MIN:MAX`: a random floating point number in [MIN, MAX)
+/// - `$string:LEN`: a random alphanumeric string of the given length
+/// - `$bool`: a random boolean
+/// - `$uuid`: a random UUID (version 4)
+/// - `$choice:A|B|C`: one of the given strings
+/// - `$n`: the dispatch index of the request
+///
+/// A leading '$$' is replaced with a literal '$'. All other values are
+/// sent as is. The values only depend on the seed and the dispatch index,
+/// so that runs with the same seed send the same bodies.
+#[derive(Debug, Clone)]
+pub struct BodyGenerator {
+    shape: Shape,
+    seed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Literal(Value),
+    Object(Vec<(String, Shape)>),
+    Array(Vec<Shape>),
+    Int(i64, i64),
+    Float(f64, f64),
+    String(usize),
+    Bool,
+    Uuid,
+    Choice(Vec<String>),
+    Index,
+}
+
+impl BodyGenerator {
+    pub fn new(spec: &str, seed: u64) -> Result<Self> {
+        let spec: Value =
+            serde_json::from_str(spec).map_err(|err| anyhow::anyhow!("invalid spec: {err}"))?;
+        Ok(Self {
+            shape: Shape::parse(spec)?,
+            seed,
+        })
+    }
+
+    /// Generates the body of the request with the dispatch index `i`.
+    pub fn render(&self, i: usize) -> String {
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(i as u64));
+        let mut out = String::new();
+        self.shape.generate(&mut rng, i, &mut out);
+        out
+    }
+
+    /// Whether the generated bodies differ between requests, i.e. they
+    /// contain the dispatch index or a UUID.
+    pub fn varies(&self) -> bool {
+        self.shape.varies()
+    }
+}
+
+impl Shape {
+    fn varies(&self) -> bool {
+        match self {
+            Self::Object(fields) => fields.iter().any(|(_, shape)| shape.varies()),
+            Self::Array(items) => items.iter().any(Self::varies),
+            Self::Uuid | Self::Index => true,
+            _ => false,
+        }
+    }
+
+    fn parse(v: Value) -> Result<Self> {
+        Ok(match v {
+            Value::String(s) => match s.strip_prefix('$') {
+                Some(rest) if rest.starts_with('$') => Self::Literal(Value::String(rest.into())),
+                Some(generator) => Self::parse_generator(generator)?,
+                None => Self::Literal(Value::String(s)),
+            },
+            Value::Object(map) => Self::Object(
+                map.into_iter()
+                    .map(|(k, v)| Ok((k, Self::parse(v)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            Value::Array(items) => {
+                Self::Array(items.into_iter().map(Self::parse).collect::<Result<_>>()?)
+            }
+            v => Self::Literal(v),
+        })
+    }
+
+    fn parse_generator(s: &str) -> Result<Self> {
+        let (name, args) = s.split_once(':').unwrap_or((s, ""));
+        let range = || {
+            args.split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("expected '${name}:MIN:MAX'"))
+        };
+
+        Ok(match name {
+            "int" => {
+                let (min, max) = range()?;
+                let (min, max) = (min.parse()?, max.parse()?);
+                if min > max {
+                    anyhow::bail!("the minimum of '${s}' exceeds its maximum");
+                }
+                Self::Int(min, max)
+            }
+            "float" => {
+                let (min, max) = range()?;
+                let (min, max): (f64, f64) = (min.parse()?, max.parse()?);
+                if min.is_nan() || max.is_nan() || min >= max {
+                    anyhow::bail!("the minimum of '${s}' must be less than its maximum");
+                }
+                Self::Float(min, max)
+            }
+            "string" => Self::String(
+                args.parse()
+                    .map_err(|_| anyhow::anyhow!("expected '$string:LEN'"))?,
+            ),
+            "choice" if !args.is_empty() => {
+                Self::Choice(args.split('|').map(String::from).collect())
+            }
+            "bool" => Self::Bool,
+            "uuid" => Self::Uuid,
+            "n" => Self::Index,
+            _ => anyhow::bail!("unknown generator: '${s}'"),
+        })
+    }
+
+    fn generate(&self, rng: &mut StdRng, i: usize, out: &mut String) {
+        match self {
+            Self::Literal(v) => out.push_str(&v.to_string()),
+            Self::Object(fields) => {
+                out.push('{');
+                for (n, (key, shape)) in fields.iter().enumerate() {
+                    if n > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(&Value::String(key.clone()).to_string());
+                    out.push(':');
+                    shape.generate(rng, i, out);
+                }
+                out.push('}');
+            }
+            Self::Array(items) => {
+                out.push('[');
+                for (n, shape) in items.iter().enumerate() {
+                    if n > 0 {
+                        out.push(',');
+                    }
+                    shape.generate(rng, i, out);
+                }
+                out.push(']');
+            }
+            Self::Int(min, max) => {
+                let _ = write!(out, "{}", rng.gen_range(*min..=*max));
+            }
+            Self::Float(min, max) => {
+                let _ = write!(out, "{}", rng.gen_range(*min..*max));
+            }
+            Self::String(len) => {
+                out.push('"');
+                out.extend(rng.sample_iter(Alphanumeric).take(*len).map(char::from));
+                out.push('"');
+            }
+            Self::Bool => out.push_str(if rng.gen() { "true" } else { "false" }),
+            Self::Uuid => {
+                let v = rng.gen::<u128>() & !(0xf000 << 64) & !(0xc << 60)
+                    | (0x4000 << 64)
+                    | (0x8 << 60);
+                let h = format!("{v:032x}");
+                let _ = write!(
+                    out,
+                    "\"{}-{}-{}-{}-{}\"",
+                    &h[..8],
+                    &h[8..12],
+                    &h[12..16],
+                    &h[16..20],
+                    &h[20..]
+                );
+            }
+            Self::Choice(options) => {
+                let choice = &options[rng.gen_range(0..options.len())];
+                out.push_str(&Value::String(choice.clone()).to_string());
+            }
+            Self::Index => {
+                let _ = write!(out, "{i}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generate() {
+        let spec = r#"{"id": "$n", "qty": "$int:1:3", "tag": "$choice:a|b",
+            "name": "$string:6", "price": "$$5", "items": ["$bool", null]}"#;
+        let generator = BodyGenerator::new(spec, 42).unwrap();
+
+        let body = generator.render(7);
+        assert_eq!(body, generator.render(7));
+        assert_ne!(body, generator.render(8));
+
+        let v: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(7, v["id"]);
+        assert!((1..=3).contains(&v["qty"].as_i64().unwrap()));
+        assert!(["a", "b"].contains(&v["tag"].as_str().unwrap()));
+        assert_eq!(6, v["name"].as_str().unwrap().len());
+        assert_eq!("$5", v["price"]);
+        assert!(v["items"][0].is_boolean() && v["items"][1].is_null());
+        // The order of the fields is kept.
+        assert!(body.starts_with(r#"{"id":7,"qty":"#));
+        assert!(generator.varies());
+        assert!(!BodyGenerator::new(r#"{"a": "$bool"}"#, 0).unwrap().varies());
+
+        assert!(BodyGenerator::new(r#"{"a": "$int:5:1"}"#, 0).is_err());
+        assert!(BodyGenerator::new(r#"{"a": "$date"}"#, 0).is_err());
+        assert!(BodyGenerator::new("{", 0).is_err());
+    }
+}
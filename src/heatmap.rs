@@ -0,0 +1,199 @@
+use crate::{duration::format_duration, output::Record};
+use anyhow::Result;
+use std::{io, time::Duration};
+
+/// The amount of time columns of the heatmap.
+const COLUMNS: usize = 60;
+
+/// The amount of latency rows of the heatmap.
+const ROWS: usize = 24;
+
+/// The size of a single cell in pixels.
+const CELL_SIZE: usize = 12;
+
+/// The space left of the cells for the latency labels in pixels.
+const MARGIN_LEFT: usize = 80;
+
+/// The space above the cells for the title in pixels.
+const MARGIN_TOP: usize = 30;
+
+/// The space below the cells for the time labels in pixels.
+const MARGIN_BOTTOM: usize = 30;
+
+/// The colors of the cells with the lowest and the highest density, in
+/// the warm tones of a flame graph.
+const COLD: (f64, f64, f64) = (255f64, 230f64, 140f64);
+const HOT: (f64, f64, f64) = (200f64, 20f64, 0f64);
+
+/// The latencies of requests binned into time columns and latency rows.
+/// The latency rows are scaled logarithmically, so that both the bulk of
+/// the requests and the tail remain visible.
+#[derive(Debug)]
+pub struct Heatmap {
+    /// The time between the first and the last request.
+    span: Duration,
+    min: Duration,
+    max: Duration,
+    /// The amount of requests per cell, row by row starting with the
+    /// lowest latencies.
+    cells: Vec<u32>,
+}
+
+impl Heatmap {
+    /// Bins the given records by their timestamp and latency. Returns
+    /// `None` if there are no records.
+    pub fn from_records(records: &[Record]) -> Option<Self> {
+        let first = records.iter().map(|r| r.timestamp).min()?;
+        let last = records.iter().map(|r| r.timestamp).max()?;
+        let min = records.iter().map(|r| r.took).min()?;
+        let max = records.iter().map(|r| r.took).max()?;
+
+        let mut heatmap = Self {
+            span: (last - first).to_std().unwrap_or_default(),
+            min,
+            max,
+            cells: vec![0; COLUMNS * ROWS],
+        };
+        for r in records {
+            let elapsed = (r.timestamp - first).to_std().unwrap_or_default();
+            let cell = heatmap.row(r.took) * COLUMNS + heatmap.column(elapsed);
+            heatmap.cells[cell] += 1;
+        }
+
+        Some(heatmap)
+    }
+
+    fn column(&self, elapsed: Duration) -> usize {
+        if self.span.is_zero() {
+            return 0;
+        }
+        let share = elapsed.as_secs_f64() / self.span.as_secs_f64();
+        ((share * COLUMNS as f64) as usize).min(COLUMNS - 1)
+    }
+
+    fn row(&self, took: Duration) -> usize {
+        let (min, max) = (self.min_nanos(), self.max.as_nanos() as f64);
+        if max <= min {
+            return 0;
+        }
+        let share = (took.as_nanos() as f64).max(min).ln() - min.ln();
+        ((share / (max.ln() - min.ln()) * ROWS as f64) as usize).min(ROWS - 1)
+    }
+
+    /// Returns the minimum latency in nanoseconds, which is at least 1 to
+    /// keep its logarithm finite.
+    fn min_nanos(&self) -> f64 {
+        (self.min.as_nanos() as f64).max(1f64)
+    }
+
+    /// Returns the lower bound of the latencies of the given row; the row
+    /// after the last one returns the maximum latency.
+    fn row_bound(&self, row: usize) -> Duration {
+        let (min, max) = (self.min_nanos(), (self.max.as_nanos() as f64).max(1f64));
+        let nanos = min * (max / min).powf(row as f64 / ROWS as f64);
+        Duration::from_nanos(nanos.round() as u64)
+    }
+
+    /// Writes the heatmap as SVG image. Cells are colored by the amount
+    /// of requests relative to the densest cell, with the latency rising
+    /// from the bottom to the top and the time from the left to the right.
+    pub fn write_svg(&self, mut w: impl io::Write) -> Result<()> {
+        let width = MARGIN_LEFT + COLUMNS * CELL_SIZE + CELL_SIZE;
+        let height = MARGIN_TOP + ROWS * CELL_SIZE + MARGIN_BOTTOM;
+        let densest = self.cells.iter().copied().max().unwrap_or_default().max(1);
+        let total: u32 = self.cells.iter().sum();
+
+        writeln!(
+            w,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="11">"#
+        )?;
+        writeln!(w, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+        writeln!(
+            w,
+            r#"<text x="{MARGIN_LEFT}" y="18" font-size="13">Latency of {total} requests over {:.1}</text>"#,
+            format_duration(self.span)
+        )?;
+
+        for (i, &n) in self.cells.iter().enumerate().filter(|(_, &n)| n > 0) {
+            let (row, column) = (i / COLUMNS, i % COLUMNS);
+            let x = MARGIN_LEFT + column * CELL_SIZE;
+            let y = MARGIN_TOP + (ROWS - 1 - row) * CELL_SIZE;
+            // The density is scaled logarithmically, so that sparse cells
+            // in the tail are not washed out by the densest ones.
+            let heat = (n as f64).ln_1p() / (densest as f64).ln_1p();
+            let channel = |cold: f64, hot: f64| (cold + (hot - cold) * heat).round() as u8;
+            writeln!(
+                w,
+                r#"<rect x="{x}" y="{y}" width="{CELL_SIZE}" height="{CELL_SIZE}" fill="rgb({},{},{})"><title>{:.4} .. {:.4}: {n} requests</title></rect>"#,
+                channel(COLD.0, HOT.0),
+                channel(COLD.1, HOT.1),
+                channel(COLD.2, HOT.2),
+                format_duration(self.row_bound(row)),
+                format_duration(self.row_bound(row + 1)),
+            )?;
+        }
+
+        for row in (0..=ROWS).step_by(4) {
+            let y = MARGIN_TOP + (ROWS - row) * CELL_SIZE;
+            writeln!(
+                w,
+                r#"<text x="{}" y="{}" text-anchor="end">{:.4}</text>"#,
+                MARGIN_LEFT - 6,
+                y + 4,
+                format_duration(self.row_bound(row))
+            )?;
+        }
+
+        for column in (0..=COLUMNS).step_by(10) {
+            let x = MARGIN_LEFT + column * CELL_SIZE;
+            let elapsed = self.span.mul_f64(column as f64 / COLUMNS as f64);
+            writeln!(
+                w,
+                r#"<text x="{x}" y="{}" text-anchor="middle">{:.1}</text>"#,
+                MARGIN_TOP + ROWS * CELL_SIZE + 18,
+                format_duration(elapsed)
+            )?;
+        }
+
+        writeln!(w, "</svg>")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn binning() {
+        let started = Utc::now();
+        let record = |s, ms| Record {
+            index: 0,
+            timestamp: started + chrono::Duration::seconds(s),
+            code: Some(200),
+            status: "200 OK".into(),
+            took: Duration::from_millis(ms),
+        };
+        let records = [
+            record(0, 1),
+            record(0, 1),
+            record(59, 1000),
+            record(30, 1000),
+        ];
+
+        let heatmap = Heatmap::from_records(&records).unwrap();
+        assert_eq!(2, heatmap.cells[0]);
+        assert_eq!(1, heatmap.cells[(ROWS - 1) * COLUMNS + COLUMNS - 1]);
+        assert_eq!(1, heatmap.cells[(ROWS - 1) * COLUMNS + COLUMNS / 2]);
+        assert_eq!(Duration::from_millis(1000), heatmap.row_bound(ROWS));
+
+        let mut svg = vec![];
+        heatmap.write_svg(&mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert_eq!(3, svg.matches("<rect x=").count());
+        assert!(svg.contains("Latency of 4 requests over 59.0s"));
+
+        assert!(Heatmap::from_records(&[]).is_none());
+    }
+}
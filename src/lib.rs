@@ -0,0 +1,68 @@
+//! The core of rush, a tiny HTTP benchmarking and performance testing
+//! tool, which can be embedded to run benchmarks programmatically.
+//!
+//! ```no_run
+//! use rush::{run_benchmark, BenchmarkConfig, ClientConfig, Load};
+//!
+//! let stats = run_benchmark(BenchmarkConfig {
+//!     client: ClientConfig {
+//!         urls: vec!["http://localhost:8080/health".into()],
+//!         methods: vec!["GET".into()],
+//!         ..Default::default()
+//!     },
+//!     load: Load::Count(100),
+//!     parallel: 4,
+//!     stack_size: None,
+//!     warmup: None,
+//!     wait: None,
+//! })
+//! .unwrap();
+//!
+//! println!("p95: {:?}", stats.p95);
+//! ```
+
+pub(crate) mod aggregate;
+pub mod bench;
+pub mod body;
+pub(crate) mod capacity;
+pub mod cli;
+pub(crate) mod compare;
+pub(crate) mod config;
+pub(crate) mod diff;
+pub mod duration;
+pub mod generator;
+#[cfg(feature = "heatmap")]
+pub(crate) mod heatmap;
+#[cfg(feature = "tui")]
+pub(crate) mod live;
+#[cfg(feature = "openapi")]
+pub(crate) mod openapi;
+pub(crate) mod output;
+pub(crate) mod overhead;
+#[cfg(feature = "parquet")]
+pub(crate) mod parquet;
+pub(crate) mod prometheus;
+pub mod rate;
+pub(crate) mod raw;
+pub(crate) mod replay;
+pub mod request;
+pub mod scenario;
+#[cfg(feature = "aws-sigv4")]
+pub mod sigv4;
+pub mod size;
+#[cfg(feature = "sqlite")]
+pub(crate) mod sqlite;
+pub mod stats;
+#[cfg(feature = "body-template")]
+pub mod template;
+#[cfg(test)]
+mod test_util;
+pub mod tls;
+pub mod token;
+#[cfg(feature = "tui")]
+pub(crate) mod tui;
+pub mod urls;
+
+pub use bench::{run_benchmark, BenchmarkConfig, Load, Warmup};
+pub use request::{Client, ClientConfig};
+pub use stats::Stats;
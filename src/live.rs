@@ -0,0 +1,147 @@
+use crate::{
+    request::{Failure, Response},
+    stats::{display_status, get_nth_percentile},
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The amount of most recent latencies the live percentiles are
+/// computed from, so that they follow changes during long runs.
+const LATENCY_WINDOW: usize = 10_000;
+
+/// The window over which the current throughput is measured.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Aggregates the results of requests as they complete, so that they
+/// can be observed while the run is still in progress.
+pub struct Live {
+    started: Instant,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    completed: u64,
+    latencies: VecDeque<Duration>,
+    completions: VecDeque<Instant>,
+    statuses: BTreeMap<String, u64>,
+}
+
+/// A point in time view of a [`Live`] aggregator.
+pub struct Snapshot {
+    pub completed: u64,
+    pub elapsed: Duration,
+    /// The amount of requests completed within the last second.
+    pub current_rps: f64,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    /// The amount of requests by their status or kind of failure.
+    pub statuses: BTreeMap<String, u64>,
+}
+
+impl Live {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            inner: Mutex::default(),
+        }
+    }
+
+    pub fn record(&self, res: &Result<Response, Failure>) {
+        let (took, status) = match res {
+            Ok(r) => (r.took, display_status(r.status.as_u16())),
+            Err(f) => (f.took, f.kind.to_string()),
+        };
+
+        let now = Instant::now();
+        let mut inner = self.inner.lock().expect("live stats lock poisoned");
+        inner.completed += 1;
+        if inner.latencies.len() == LATENCY_WINDOW {
+            inner.latencies.pop_front();
+        }
+        inner.latencies.push_back(took);
+        inner.completions.push_back(now);
+        inner.prune(now);
+        *inner.statuses.entry(status).or_default() += 1;
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let now = Instant::now();
+        let mut inner = self.inner.lock().expect("live stats lock poisoned");
+        inner.prune(now);
+
+        let mut times: Vec<_> = inner.latencies.iter().copied().collect();
+        times.sort();
+        let percentile = |p| {
+            if times.is_empty() {
+                Duration::ZERO
+            } else {
+                get_nth_percentile(&times, p)
+            }
+        };
+
+        Snapshot {
+            completed: inner.completed,
+            elapsed: now - self.started,
+            current_rps: inner.completions.len() as f64 / THROUGHPUT_WINDOW.as_secs_f64(),
+            p50: percentile(0.5),
+            p90: percentile(0.9),
+            p99: percentile(0.99),
+            statuses: inner.statuses.clone(),
+        }
+    }
+}
+
+impl Default for Live {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inner {
+    /// Removes the completions which are older than the throughput window.
+    fn prune(&mut self, now: Instant) {
+        while let Some(&t) = self.completions.front() {
+            if now - t <= THROUGHPUT_WINDOW {
+                break;
+            }
+            self.completions.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{request::FailureKind, test_util};
+    use chrono::Utc;
+    use reqwest::Method;
+
+    #[test]
+    fn snapshot() {
+        let live = Live::new();
+        for ms in 1..=10 {
+            live.record(&Ok(test_util::response(ms)));
+        }
+        live.record(&Err(Failure {
+            index: 0,
+            method: Method::GET,
+            kind: FailureKind::Timeout,
+            message: String::new(),
+            reason: String::new(),
+            took: Duration::from_millis(100),
+            timestamp: Utc::now(),
+        }));
+
+        let s = live.snapshot();
+        assert_eq!(11, s.completed);
+        assert_eq!(11f64, s.current_rps);
+        assert_eq!(Some(&10), s.statuses.get("200 OK"));
+        assert_eq!(Some(&1), s.statuses.get("timeout"));
+        assert!(s.p50 > Duration::from_millis(5) && s.p50 < Duration::from_millis(7));
+    }
+}
@@ -0,0 +1,316 @@
+use crate::scenario::Target;
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::{fs::File, io::BufReader, path::Path};
+
+/// The fields of a path item which describe an operation.
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// The depth up to which nested schemas are turned into example values,
+/// which also stops recursive schemas.
+const MAX_DEPTH: usize = 8;
+
+/// An OpenAPI 3 or Swagger 2 spec in the JSON format.
+pub struct Spec {
+    root: Value,
+}
+
+impl Spec {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let f = File::open(path)?;
+        Self::new(serde_json::from_reader(BufReader::new(f))?)
+    }
+
+    fn new(root: Value) -> Result<Self> {
+        if root.get("paths").and_then(Value::as_object).is_none() {
+            anyhow::bail!("the spec does not contain any paths");
+        }
+        Ok(Self { root })
+    }
+
+    /// Returns a target per operation, named after its method and path,
+    /// whose URL is the path appended to `base`. Path parameters and
+    /// required query parameters and headers are filled in with their
+    /// example values, which are generated from the schemas if the spec
+    /// does not provide any, and so are the request bodies.
+    pub fn targets(&self, base: &str) -> Result<Vec<Target>> {
+        let base = base.trim_end_matches('/');
+        let mut targets = vec![];
+
+        for (path, item) in self.root["paths"].as_object().into_iter().flatten() {
+            let item = self.resolve(item)?;
+            for &method in METHODS {
+                let Some(op) = item.get(method) else {
+                    continue;
+                };
+                let mut target = self
+                    .target(base, path, item, op)
+                    .map_err(|err| anyhow::anyhow!("invalid operation {method} {path}: {err}"))?;
+                let method = method.to_uppercase();
+                target.name = Some(format!("{method} {path}"));
+                target.method = Some(method);
+                targets.push(target);
+            }
+        }
+
+        if targets.is_empty() {
+            anyhow::bail!("the spec does not contain any operations");
+        }
+        Ok(targets)
+    }
+
+    /// Returns the target of the operation of the path item, without its
+    /// name and method.
+    fn target(&self, base: &str, path: &str, item: &Value, op: &Value) -> Result<Target> {
+        let mut params: Vec<&Value> = vec![];
+        for param in parameters(item).chain(parameters(op)) {
+            let param = self.resolve(param)?;
+            // Parameters of the operation override the ones of the path.
+            params.retain(|p| p["name"] != param["name"] || p["in"] != param["in"]);
+            params.push(param);
+        }
+
+        let mut target = Target {
+            url: format!("{base}{path}"),
+            ..Default::default()
+        };
+        let mut query = vec![];
+        for param in params {
+            let name = param["name"].as_str().unwrap_or_default();
+            let required = param["required"].as_bool().unwrap_or_default();
+            match param["in"].as_str() {
+                Some("path") => {
+                    target.url = target
+                        .url
+                        .replace(&format!("{{{name}}}"), &self.param_value(param))
+                }
+                Some("query") if required => {
+                    query.push(format!("{name}={}", self.param_value(param)))
+                }
+                Some("header") if required => target
+                    .headers
+                    .push(format!("{name}: {}", self.param_value(param))),
+                Some("body") => {
+                    let consumes = op.get("consumes").or_else(|| self.root.get("consumes"));
+                    let content_type = consumes
+                        .and_then(|c| c[0].as_str())
+                        .unwrap_or("application/json");
+                    let example = self.example(&param["schema"], 0);
+                    target.body = Some(body(content_type, &example));
+                    target.headers.push(format!("Content-Type: {content_type}"));
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(request_body) = op.get("requestBody") {
+            let content = self.resolve(request_body)?["content"].as_object();
+            let media = content.and_then(|c| {
+                c.get_key_value("application/json")
+                    .or_else(|| c.iter().next())
+            });
+            if let Some((content_type, media)) = media {
+                let example = match (media.get("example"), media.get("examples")) {
+                    (Some(example), _) => example.clone(),
+                    (None, Some(Value::Object(examples))) if !examples.is_empty() => {
+                        let (_, example) = examples.iter().next().expect("examples are not empty");
+                        self.resolve(example)?["value"].clone()
+                    }
+                    _ => self.example(&media["schema"], 0),
+                };
+                target.body = Some(body(content_type, &example));
+                target.headers.push(format!("Content-Type: {content_type}"));
+            }
+        }
+
+        if !query.is_empty() {
+            target.url = format!("{}?{}", target.url, query.join("&"));
+        }
+        Ok(target)
+    }
+
+    /// Returns the example value of the parameter as it is sent.
+    fn param_value(&self, param: &Value) -> String {
+        let example = match (param.get("example"), param.get("examples")) {
+            (Some(example), _) => example.clone(),
+            (None, Some(Value::Object(examples))) if !examples.is_empty() => {
+                let (_, example) = examples.iter().next().expect("examples are not empty");
+                self.resolve(example)
+                    .map(|e| e["value"].clone())
+                    .unwrap_or_default()
+            }
+            // Swagger 2 describes the type of the parameter itself.
+            _ => self.example(param.get("schema").unwrap_or(param), 0),
+        };
+        match example {
+            Value::String(s) => s,
+            v => v.to_string(),
+        }
+    }
+
+    /// Returns the example of the schema, its default or a value
+    /// generated from its type.
+    fn example(&self, schema: &Value, depth: usize) -> Value {
+        let Ok(schema) = self.resolve(schema) else {
+            return Value::Null;
+        };
+        if depth > MAX_DEPTH {
+            return Value::Null;
+        }
+        if let Some(v) = schema.get("example").or_else(|| schema.get("default")) {
+            return v.clone();
+        }
+        if let Some(v) = schema["enum"].get(0) {
+            return v.clone();
+        }
+        if let Some(all) = schema["allOf"].as_array() {
+            let mut merged = Map::new();
+            for s in all {
+                if let Value::Object(fields) = self.example(s, depth + 1) {
+                    merged.extend(fields);
+                }
+            }
+            return Value::Object(merged);
+        }
+        if let Some(s) = schema["oneOf"].get(0).or_else(|| schema["anyOf"].get(0)) {
+            return self.example(s, depth + 1);
+        }
+
+        let ty = match &schema["type"] {
+            // OpenAPI 3.1 allows a list of types, e.g. with 'null'.
+            Value::Array(types) => types.iter().find(|t| *t != "null").unwrap_or(&Value::Null),
+            ty => ty,
+        };
+        match ty.as_str() {
+            Some("object") | None if schema.get("properties").is_some() => {
+                let properties = schema["properties"].as_object().into_iter().flatten();
+                Value::Object(
+                    properties
+                        .map(|(k, s)| (k.clone(), self.example(s, depth + 1)))
+                        .collect(),
+                )
+            }
+            Some("object") => Value::Object(Map::new()),
+            Some("array") => Value::Array(vec![self.example(&schema["items"], depth + 1)]),
+            Some("string") => Value::from(match schema["format"].as_str() {
+                Some("date-time") => "2024-01-01T00:00:00Z",
+                Some("date") => "2024-01-01",
+                Some("uuid") => "00000000-0000-0000-0000-000000000000",
+                Some("email") => "user@example.com",
+                Some("uri") => "https://example.com",
+                _ => "string",
+            }),
+            Some("integer") => schema["minimum"].as_i64().unwrap_or(1).into(),
+            Some("number") => schema["minimum"].as_f64().unwrap_or(1.0).into(),
+            Some("boolean") => true.into(),
+            _ => Value::Null,
+        }
+    }
+
+    /// Follows the reference of the value, if it is one; only references
+    /// within the spec are supported.
+    fn resolve<'a>(&'a self, value: &'a Value) -> Result<&'a Value> {
+        let mut value = value;
+        // Limits chains of references, which may be cyclic.
+        for _ in 0..MAX_DEPTH {
+            let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+                return Ok(value);
+            };
+            let Some(pointer) = reference.strip_prefix('#') else {
+                anyhow::bail!("unsupported reference to another file: '{reference}'");
+            };
+            value = self
+                .root
+                .pointer(pointer)
+                .ok_or_else(|| anyhow::anyhow!("unresolved reference: '{reference}'"))?;
+        }
+        anyhow::bail!("too deeply nested references")
+    }
+}
+
+fn parameters(value: &Value) -> impl Iterator<Item = &Value> {
+    value["parameters"].as_array().into_iter().flatten()
+}
+
+/// Returns the example as the body of the given content type; strings
+/// are sent as is unless JSON is sent.
+fn body(content_type: &str, example: &Value) -> String {
+    match example {
+        Value::String(s) if !content_type.contains("json") => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn targets() {
+        let spec = Spec::new(json!({
+            "openapi": "3.0.0",
+            "paths": {
+                "/pets": {
+                    "get": {
+                        "parameters": [
+                            {"name": "limit", "in": "query", "required": true, "schema": {"type": "integer", "minimum": 5}},
+                            {"name": "tag", "in": "query", "schema": {"type": "string"}},
+                        ],
+                    },
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {"schema": {"$ref": "#/components/schemas/Pet"}},
+                            },
+                        },
+                    },
+                },
+                "/pets/{petId}": {
+                    "parameters": [{"$ref": "#/components/parameters/PetId"}],
+                    "delete": {
+                        "parameters": [{"name": "X-Key", "in": "header", "required": true, "example": "abc"}],
+                    },
+                },
+            },
+            "components": {
+                "parameters": {
+                    "PetId": {"name": "petId", "in": "path", "required": true, "example": 42},
+                },
+                "schemas": {
+                    "Pet": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string", "example": "Rex"},
+                            "tags": {"type": "array", "items": {"type": "string", "enum": ["a", "b"]}},
+                            "parent": {"$ref": "#/components/schemas/Pet"},
+                        },
+                    },
+                },
+            },
+        }))
+        .unwrap();
+
+        let targets = spec.targets("http://localhost/api/").unwrap();
+        assert_eq!(3, targets.len());
+
+        assert_eq!(Some("GET /pets"), targets[0].name.as_deref());
+        assert_eq!("http://localhost/api/pets?limit=5", targets[0].url);
+        assert!(targets[0].body.is_none());
+
+        assert_eq!(Some("POST"), targets[1].method.as_deref());
+        assert_eq!(vec!["Content-Type: application/json"], targets[1].headers);
+        let body: Value = serde_json::from_str(targets[1].body.as_ref().unwrap()).unwrap();
+        assert_eq!("Rex", body["name"]);
+        assert_eq!(json!(["a"]), body["tags"]);
+        assert_eq!("Rex", body["parent"]["parent"]["name"]);
+
+        assert_eq!(Some("DELETE /pets/{petId}"), targets[2].name.as_deref());
+        assert_eq!("http://localhost/api/pets/42", targets[2].url);
+        assert_eq!(vec!["X-Key: abc"], targets[2].headers);
+
+        assert!(Spec::new(json!({"swagger": "2.0"})).is_err());
+    }
+}
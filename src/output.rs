@@ -0,0 +1,617 @@
+use crate::{
+    duration::format_duration,
+    request::{Failure, Response},
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// A single row of the per request output.
+pub struct Record {
+    /// The dispatch index of the request.
+    pub index: usize,
+    pub timestamp: DateTime<Utc>,
+    /// The response status code; unset for failures.
+    pub code: Option<u16>,
+    /// The response status or the kind of failure.
+    pub status: String,
+    pub took: Duration,
+}
+
+impl From<&Response> for Record {
+    fn from(r: &Response) -> Self {
+        Self {
+            index: r.index,
+            timestamp: r.timestamp,
+            code: Some(r.status.as_u16()),
+            status: r.status_label(),
+            took: r.took,
+        }
+    }
+}
+
+impl From<&Failure> for Record {
+    fn from(f: &Failure) -> Self {
+        Self {
+            index: f.index,
+            timestamp: f.timestamp,
+            code: None,
+            status: f.kind.to_string(),
+            took: f.took,
+        }
+    }
+}
+
+/// Returns the records of all responses and failures sorted by their
+/// timestamp.
+pub fn records(res: &[Response], failures: &[Failure]) -> Vec<Record> {
+    let mut records: Vec<_> = res
+        .iter()
+        .map(Record::from)
+        .chain(failures.iter().map(Record::from))
+        .collect();
+    records.sort_by_key(|r| r.timestamp);
+    records
+}
+
+/// Selects records by their status code, status class or kind of failure.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusPattern {
+    /// An exact status code, e.g. '404'.
+    Code(u16),
+    /// A class of status codes by their first digit, e.g. '5xx'.
+    Class(u16),
+    /// A kind of failure, e.g. 'timeout'.
+    Failure(String),
+}
+
+impl StatusPattern {
+    pub fn matches(&self, r: &Record) -> bool {
+        match (self, r.code) {
+            (Self::Code(c), Some(code)) => *c == code,
+            (Self::Class(c), Some(code)) => *c == code / 100,
+            (Self::Failure(kind), None) => *kind == r.status,
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for StatusPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+
+        if let Some(class) = s.strip_suffix("xx") {
+            return match class.parse() {
+                Ok(c @ 1..=5) => Ok(Self::Class(c)),
+                _ => anyhow::bail!("invalid status class: '{s}'"),
+            };
+        }
+
+        if let Ok(code) = s.parse() {
+            return Ok(Self::Code(code));
+        }
+
+        match s.as_str() {
+            "dns" | "connect" | "tls" | "timeout" | "error" => Ok(Self::Failure(s)),
+            _ => anyhow::bail!(
+                "invalid status: '{s}'; expected a code, a class like '5xx' \
+                or one of 'dns', 'connect', 'tls', 'timeout' and 'error'"
+            ),
+        }
+    }
+}
+
+pub fn get_output_file(path: &str) -> Result<File> {
+    let pth = Path::new(&path);
+
+    let f = if pth.exists() {
+        File::options().append(true).open(pth)
+    } else {
+        create_parent_dirs(pth)?;
+        File::create(pth)
+    }?;
+
+    Ok(f)
+}
+
+/// Returns whether the path points to a FIFO, i.e. a named pipe, which
+/// is read by another process.
+#[cfg(unix)]
+pub fn is_fifo(path: &str) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+pub fn is_fifo(_path: &str) -> bool {
+    false
+}
+
+/// Opens the FIFO at the given path for writing, which blocks until
+/// another process opens it for reading.
+pub fn open_fifo(path: &str) -> Result<File> {
+    Ok(File::options().write(true).open(path)?)
+}
+
+pub fn get_output_file_truncated(path: &str) -> Result<File> {
+    let pth = Path::new(&path);
+    create_parent_dirs(pth)?;
+    Ok(File::create(pth)?)
+}
+
+fn create_parent_dirs(pth: &Path) -> Result<()> {
+    if let Some(parent) = pth.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the records formatted as CSV; `index` appends the dispatch
+/// index of each request as last column.
+pub fn write_csv(mut w: impl io::Write, records: &[Record], index: bool) -> Result<()> {
+    for r in records {
+        write_csv_record(&mut w, r, index)?;
+    }
+
+    Ok(())
+}
+
+fn write_csv_record(mut w: impl io::Write, r: &Record, index: bool) -> io::Result<()> {
+    write!(w, "{},{},{}", r.timestamp, r.status, r.took.as_nanos())?;
+    if index {
+        write!(w, ",{}", r.index)?;
+    }
+    writeln!(w)
+}
+
+/// The limits after which the CSV output file is rotated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rotation {
+    /// The maximum size of a file in bytes.
+    pub max_size: Option<u64>,
+    /// The maximum time span between the first and the last record of a
+    /// file.
+    pub interval: Option<Duration>,
+}
+
+/// Writes records formatted as CSV to a file, which is rotated as soon as
+/// the next record would exceed the limits of the [`Rotation`]. The full
+/// file is renamed by appending the next free number to its path, e.g.
+/// 'out.csv' to 'out.csv.1', 'out.csv.2' and so on, so that higher
+/// numbers contain newer records and the given path the newest ones.
+pub struct RotatingWriter {
+    path: PathBuf,
+    rotation: Rotation,
+    file: File,
+    size: u64,
+    /// The timestamp of the first record written to the current file.
+    started: Option<DateTime<Utc>>,
+    /// Whether the dispatch index is written as last column.
+    index: bool,
+}
+
+impl RotatingWriter {
+    /// Opens the file at the given path, appending it if it already
+    /// exists; `index` appends the dispatch index to each record.
+    pub fn open(path: &str, rotation: Rotation, index: bool) -> Result<Self> {
+        let file = get_output_file(path)?;
+        Ok(Self {
+            path: path.into(),
+            rotation,
+            size: file.metadata()?.len(),
+            file,
+            started: None,
+            index,
+        })
+    }
+
+    pub fn write(&mut self, records: &[Record]) -> Result<()> {
+        let mut line = vec![];
+        for r in records {
+            line.clear();
+            write_csv_record(&mut line, r, self.index)?;
+
+            let too_large = self
+                .rotation
+                .max_size
+                .is_some_and(|max| self.size > 0 && self.size + line.len() as u64 > max);
+            let too_old = match (self.rotation.interval, self.started) {
+                (Some(interval), Some(started)) => {
+                    (r.timestamp - started).to_std().unwrap_or_default() >= interval
+                }
+                _ => false,
+            };
+            if too_large || too_old {
+                self.rotate()?;
+            }
+
+            self.file.write_all(&line)?;
+            self.size += line.len() as u64;
+            self.started.get_or_insert(r.timestamp);
+        }
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let rotated = (1..)
+            .map(|n| {
+                let mut path = self.path.clone().into_os_string();
+                path.push(format!(".{n}"));
+                PathBuf::from(path)
+            })
+            .find(|p| !p.exists())
+            .expect("no free rotation suffix");
+
+        self.file.flush()?;
+        fs::rename(&self.path, rotated)?;
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        self.started = None;
+        Ok(())
+    }
+}
+
+/// Writes the record of each request formatted as CSV as soon as the
+/// request completes, e.g. to a FIFO read by another process. Each row is
+/// written by a single write, so that the reader never sees partial rows,
+/// and the stream stops at the first error, e.g. once the reader is gone.
+pub struct CsvStream {
+    /// The file until writing to it failed, or the error.
+    file: Mutex<Result<File, String>>,
+    /// The patterns a record has to match any of to be written; all
+    /// records are written if empty.
+    filter: Vec<StatusPattern>,
+}
+
+impl CsvStream {
+    pub fn new(file: File, filter: Vec<StatusPattern>) -> Self {
+        Self {
+            file: Mutex::new(Ok(file)),
+            filter,
+        }
+    }
+
+    pub fn record(&self, res: &Result<Response, Failure>) {
+        let record = match res {
+            Ok(r) => Record::from(r),
+            Err(f) => Record::from(f),
+        };
+        if !self.filter.is_empty() && !self.filter.iter().any(|p| p.matches(&record)) {
+            return;
+        }
+
+        let mut line = vec![];
+        // Streams never write the index, since `ordered` cannot be used
+        // with them.
+        write_csv_record(&mut line, &record, false).expect("writing to a vector never fails");
+        let mut file = self.file.lock().expect("stream lock poisoned");
+        if let Ok(f) = &mut *file {
+            if let Err(err) = f.write_all(&line).and_then(|_| f.flush()) {
+                *file = Err(err.to_string());
+            }
+        }
+    }
+
+    /// Returns the error which stopped the stream, if any.
+    pub fn error(&self) -> Option<String> {
+        self.file
+            .lock()
+            .expect("stream lock poisoned")
+            .as_ref()
+            .err()
+            .cloned()
+    }
+}
+
+/// Writes the requests which took longer than the threshold formatted
+/// as CSV, followed by a column for each of the `captured` response
+/// headers, which are empty for failures. Returns the amount of written
+/// requests.
+pub fn write_slow_log(
+    mut w: impl io::Write,
+    res: &[Response],
+    failures: &[Failure],
+    threshold: Duration,
+    captured: usize,
+) -> Result<usize> {
+    let no_headers = vec![None; captured];
+    let mut slow: Vec<_> = res
+        .iter()
+        .filter(|r| r.took > threshold)
+        .map(|r| (r.timestamp, r.status_label(), r.took, &r.captured))
+        .chain(
+            failures
+                .iter()
+                .filter(|f| f.took > threshold)
+                .map(|f| (f.timestamp, f.kind.to_string(), f.took, &no_headers)),
+        )
+        .collect();
+    slow.sort_by_key(|(timestamp, ..)| *timestamp);
+
+    for (timestamp, status, took, headers) in &slow {
+        write!(w, "{timestamp},{status},{}", took.as_nanos())?;
+        for value in headers.iter() {
+            write!(w, ",{}", csv_field(value.as_deref().unwrap_or_default()))?;
+        }
+        writeln!(w)?;
+    }
+
+    Ok(slow.len())
+}
+
+/// The minimum time between two alerts about slow requests.
+const ALERT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Warns about each request which took longer than the threshold as
+/// soon as it completes. At most one warning is written per
+/// [`ALERT_INTERVAL`]; the slow requests in between are counted and
+/// reported with the next warning.
+pub struct SlowAlert {
+    threshold: Duration,
+    state: Mutex<AlertState>,
+}
+
+#[derive(Default)]
+struct AlertState {
+    /// The time of the last written warning.
+    last: Option<Instant>,
+    /// The amount of slow requests since the last written warning.
+    suppressed: usize,
+}
+
+impl SlowAlert {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Writes a warning to stderr if the request was slow and no warning
+    /// has been written within the interval. The line is written while
+    /// stderr is locked, so that warnings about concurrently completed
+    /// requests never interleave.
+    pub fn record(&self, res: &Result<Response, Failure>) {
+        if let Some(line) = self.alert(res, Instant::now()) {
+            let _ = writeln!(io::stderr().lock(), "{line}");
+        }
+    }
+
+    /// Returns the warning about the request completed at `now`, if any.
+    fn alert(&self, res: &Result<Response, Failure>, now: Instant) -> Option<String> {
+        let (index, status, took) = match res {
+            Ok(r) => (r.index, r.status_label(), r.took),
+            Err(f) => (f.index, f.kind.to_string(), f.took),
+        };
+        if took <= self.threshold {
+            return None;
+        }
+
+        let mut state = self.state.lock().expect("alert lock poisoned");
+        if state
+            .last
+            .is_some_and(|last| now.duration_since(last) < ALERT_INTERVAL)
+        {
+            state.suppressed += 1;
+            return None;
+        }
+
+        let mut line = format!(
+            "warning: request {index} took {:.4} ({status}), exceeding {}",
+            format_duration(took),
+            format_duration(self.threshold),
+        );
+        if state.suppressed > 0 {
+            line.push_str(&format!(
+                "; {} more slow requests since the last warning",
+                state.suppressed
+            ));
+        }
+        *state = AlertState {
+            last: Some(now),
+            suppressed: 0,
+        };
+        Some(line)
+    }
+}
+
+/// Quotes the field if it contains a separator, a quote or a line break.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{request::FailureKind, test_util};
+    use reqwest::{Method, StatusCode};
+
+    fn record(code: Option<u16>, status: &str) -> Record {
+        Record {
+            index: 0,
+            timestamp: Utc::now(),
+            code,
+            status: status.into(),
+            took: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn status_pattern() {
+        let ok = record(Some(200), "200 OK");
+        let unavailable = record(Some(503), "503 Service Unavailable");
+        let timeout = record(None, "timeout");
+
+        let p: StatusPattern = "5XX".parse().unwrap();
+        assert!(p.matches(&unavailable) && !p.matches(&ok) && !p.matches(&timeout));
+
+        let p: StatusPattern = "200".parse().unwrap();
+        assert!(p.matches(&ok) && !p.matches(&unavailable));
+
+        let p: StatusPattern = "timeout".parse().unwrap();
+        assert!(p.matches(&timeout) && !p.matches(&ok));
+
+        assert!("9xx".parse::<StatusPattern>().is_err());
+        assert!("slow".parse::<StatusPattern>().is_err());
+    }
+
+    #[test]
+    fn csv_index() {
+        let records = [Record {
+            index: 7,
+            ..record(Some(200), "200 OK")
+        }];
+        let mut out = vec![];
+        write_csv(&mut out, &records, false).unwrap();
+        write_csv(&mut out, &records, true).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert!(lines[0].ends_with(",200 OK,0"));
+        assert!(lines[1].ends_with(",200 OK,0,7"));
+    }
+
+    #[test]
+    fn rotation() {
+        let dir = test_util::temp_dir("rotation");
+        let path = dir.join("out.csv");
+
+        let started = Utc::now();
+        let records: Vec<_> = (0..5)
+            .map(|s| Record {
+                timestamp: started + chrono::Duration::seconds(s),
+                ..record(Some(200), "200 OK")
+            })
+            .collect();
+        let line_len = {
+            let mut line = vec![];
+            write_csv_record(&mut line, &records[0], false).unwrap();
+            line.len() as u64
+        };
+
+        let rotation = Rotation {
+            max_size: Some(line_len * 2),
+            interval: None,
+        };
+        let mut w = RotatingWriter::open(path.to_str().unwrap(), rotation, false).unwrap();
+        w.write(&records).unwrap();
+        let lines = |p: &Path| fs::read_to_string(p).unwrap().lines().count();
+        assert_eq!(2, lines(&dir.join("out.csv.1")));
+        assert_eq!(2, lines(&dir.join("out.csv.2")));
+        assert_eq!(1, lines(&path));
+
+        fs::remove_dir_all(&dir).unwrap();
+        let rotation = Rotation {
+            max_size: None,
+            interval: Some(Duration::from_secs(3)),
+        };
+        let mut w = RotatingWriter::open(path.to_str().unwrap(), rotation, false).unwrap();
+        w.write(&records).unwrap();
+        assert_eq!(3, lines(&dir.join("out.csv.1")));
+        assert_eq!(2, lines(&path));
+    }
+
+    fn response(ms: u64, captured: Vec<Option<String>>) -> Response {
+        Response {
+            captured,
+            ..test_util::response(ms)
+        }
+    }
+
+    #[test]
+    fn slow_log() {
+        let res = [
+            response(5, vec![Some("fast".into()), None]),
+            response(50, vec![Some("a,\"b\"".into()), None]),
+        ];
+        let failures = [Failure {
+            index: 0,
+            method: Method::GET,
+            kind: FailureKind::Timeout,
+            message: String::new(),
+            reason: String::new(),
+            took: Duration::from_millis(100),
+            timestamp: Utc::now(),
+        }];
+
+        let mut out = vec![];
+        let n = write_slow_log(&mut out, &res, &failures, Duration::from_millis(10), 2).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+
+        assert_eq!(2, n);
+        assert!(lines[0].ends_with(",200 OK,50000000,\"a,\"\"b\"\"\","));
+        assert!(lines[1].ends_with(",timeout,100000000,,"));
+    }
+
+    #[test]
+    fn csv_stream() {
+        let dir = test_util::temp_dir("stream");
+        let path = dir.join("out.csv");
+        let f = get_output_file_truncated(path.to_str().unwrap()).unwrap();
+        assert!(!is_fifo(path.to_str().unwrap()));
+
+        let stream = CsvStream::new(f, vec!["5xx".parse().unwrap(), "timeout".parse().unwrap()]);
+        stream.record(&Ok(response(5, vec![])));
+        stream.record(&Ok(Response {
+            status: StatusCode::BAD_GATEWAY,
+            ..response(10, vec![])
+        }));
+        stream.record(&Err(Failure {
+            index: 1,
+            method: Method::GET,
+            kind: FailureKind::Timeout,
+            message: String::new(),
+            reason: String::new(),
+            took: Duration::from_millis(20),
+            timestamp: Utc::now(),
+        }));
+
+        let out = fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].ends_with(",502 Bad Gateway,10000000"));
+        assert!(lines[1].ends_with(",timeout,20000000"));
+        assert_eq!(None, stream.error());
+    }
+
+    #[test]
+    fn slow_alert() {
+        let alert = SlowAlert::new(Duration::from_millis(10));
+        let started = Instant::now();
+        let at = |ms| started + Duration::from_millis(ms);
+        let slow = Ok(response(50, vec![]));
+
+        assert_eq!(None, alert.alert(&Ok(response(5, vec![])), at(0)));
+        assert_eq!(
+            Some("warning: request 0 took 50.0000ms (200 OK), exceeding 10ms".into()),
+            alert.alert(&slow, at(0))
+        );
+        assert_eq!(None, alert.alert(&slow, at(500)));
+        assert_eq!(None, alert.alert(&slow, at(900)));
+        assert_eq!(
+            Some(
+                "warning: request 0 took 50.0000ms (200 OK), exceeding 10ms; 2 more slow requests \
+                since the last warning"
+                    .into()
+            ),
+            alert.alert(&slow, at(1000))
+        );
+    }
+}
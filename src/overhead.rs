@@ -0,0 +1,179 @@
+use crate::{
+    bench::{build_runtime, measure, Concurrency, Load},
+    request::{Client, ClientConfig},
+};
+use anyhow::Result;
+use chrono::Utc;
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The amount of requests sent before the measured ones, so that the
+/// connection is established and the allocations have settled.
+const WARMUP: u32 = 100;
+
+/// The amount of iterations over which the cost of taking the
+/// timestamps of a request is averaged.
+const TIMING_ITERATIONS: u32 = 100_000;
+
+/// The overhead of rush itself, measured against a local server which
+/// answers each request immediately.
+#[derive(Debug)]
+pub struct Overhead {
+    pub requests: u32,
+    /// The wall time per request which is not covered by the measured
+    /// latencies, i.e. the time spent dispatching the request and
+    /// collecting its result.
+    pub dispatch: Duration,
+    /// The cost of taking the timestamps of a single request, which is
+    /// included in the measured latencies.
+    pub timing: Duration,
+    /// The median latency against the local server, which is the lowest
+    /// latency rush can measure on this machine.
+    pub floor: Duration,
+}
+
+impl Overhead {
+    /// Sends the given amount of requests one after another to a local
+    /// server and measures the overhead of rush.
+    pub fn measure(requests: u32) -> Result<Self> {
+        let server = LocalServer::start()?;
+        let client = Arc::new(Client::new(ClientConfig {
+            urls: vec![format!("http://{}/", server.addr)],
+            methods: vec!["GET".into()],
+            ..Default::default()
+        })?);
+        let runtime = build_runtime(1, None)?;
+
+        let run = |n| {
+            measure(
+                &runtime,
+                &client,
+                &Load::Count(n),
+                Concurrency::Fixed(1),
+                None,
+                None,
+                None,
+            )
+        };
+        run(WARMUP);
+        let result = run(requests);
+        if let Some(f) = result.failures.first() {
+            anyhow::bail!("a request to the local server failed: {}", f.message);
+        }
+
+        let mut times: Vec<_> = result.responses.iter().map(|r| r.took).collect();
+        times.sort();
+        let measured: Duration = times.iter().sum();
+
+        Ok(Self {
+            requests,
+            dispatch: result.elapsed.saturating_sub(measured) / requests,
+            timing: timing_cost(),
+            floor: times[times.len() / 2],
+        })
+    }
+
+    pub fn print(&self) {
+        println!(
+            "Self-benchmark of {} requests against a local server:\n\
+            \n\
+            Dispatch:   {:>7} ns/request  (not included in the latencies)\n\
+            Timing:     {:>7} ns/request  (included in the latencies)\n\
+            Floor:      {:>7} ns          (median latency)\
+            ",
+            self.requests,
+            self.dispatch.as_nanos(),
+            self.timing.as_nanos(),
+            self.floor.as_nanos(),
+        );
+    }
+}
+
+/// Returns the average cost of the timestamps taken for each request,
+/// i.e. the start and end instants and the wall clock timestamp.
+fn timing_cost() -> Duration {
+    let started = Instant::now();
+    for _ in 0..TIMING_ITERATIONS {
+        std::hint::black_box((Instant::now(), Utc::now(), Instant::now()));
+    }
+    started.elapsed() / TIMING_ITERATIONS
+}
+
+/// A minimal HTTP/1.1 server answering each request with 204 over
+/// persistent connections, which runs until it is dropped.
+struct LocalServer {
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+}
+
+impl LocalServer {
+    fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let stop = stopped.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    thread::spawn(move || serve(stream));
+                }
+            }
+        });
+
+        Ok(Self { addr, stopped })
+    }
+}
+
+impl Drop for LocalServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Wakes up the listener blocked on accepting connections.
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// Answers the requests of the connection until it is closed; request
+/// bodies are not expected.
+fn serve(stream: TcpStream) {
+    let _ = stream.set_nodelay(true);
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) if line == "\r\n" => {
+                let res = b"HTTP/1.1 204 No Content\r\n\r\n";
+                if (&stream).write_all(res).is_err() {
+                    return;
+                }
+            }
+            Ok(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn self_benchmark() {
+        let overhead = Overhead::measure(50).unwrap();
+        assert_eq!(50, overhead.requests);
+        assert!(!overhead.floor.is_zero());
+        assert!(overhead.timing < Duration::from_millis(1));
+    }
+}
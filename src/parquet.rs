@@ -0,0 +1,97 @@
+use crate::output::Record;
+use anyhow::Result;
+use parquet::{
+    data_type::{ByteArray, ByteArrayType, Int64Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
+};
+use std::{io, sync::Arc};
+
+const SCHEMA: &str = "
+message rush {
+    REQUIRED INT64 timestamp (TIMESTAMP(NANOS, true));
+    REQUIRED BYTE_ARRAY status (UTF8);
+    REQUIRED INT64 took_ns;
+}
+";
+
+/// Writes the given records as a single row group Parquet file using
+/// the same columns as the CSV output.
+pub fn write_parquet(w: impl io::Write + Send, records: &[Record]) -> Result<()> {
+    let schema = Arc::new(parse_message_type(SCHEMA)?);
+    let props = Arc::new(WriterProperties::builder().build());
+    let mut writer = SerializedFileWriter::new(w, schema, props)?;
+
+    let timestamps: Vec<_> = records
+        .iter()
+        .map(|r| r.timestamp.timestamp_nanos_opt().unwrap_or_default())
+        .collect();
+    let statuses: Vec<_> = records
+        .iter()
+        .map(|r| ByteArray::from(r.status.as_str()))
+        .collect();
+    let took: Vec<_> = records.iter().map(|r| r.took.as_nanos() as i64).collect();
+
+    let mut row_group = writer.next_row_group()?;
+    let mut i = 0;
+    while let Some(mut col) = row_group.next_column()? {
+        match i {
+            0 => col
+                .typed::<Int64Type>()
+                .write_batch(&timestamps, None, None)?,
+            1 => col
+                .typed::<ByteArrayType>()
+                .write_batch(&statuses, None, None)?,
+            _ => col.typed::<Int64Type>().write_batch(&took, None, None)?,
+        };
+        col.close()?;
+        i += 1;
+    }
+    row_group.close()?;
+    writer.close()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util;
+    use chrono::Utc;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use std::{fs::File, time::Duration};
+
+    #[test]
+    fn roundtrip() {
+        let records = vec![
+            Record {
+                index: 0,
+                timestamp: Utc::now(),
+                code: Some(200),
+                status: "200 OK".into(),
+                took: Duration::from_millis(5),
+            },
+            Record {
+                index: 0,
+                timestamp: Utc::now(),
+                code: None,
+                status: "timeout".into(),
+                took: Duration::from_millis(7),
+            },
+        ];
+
+        let path = test_util::temp_dir("parquet").join("roundtrip.parquet");
+        write_parquet(File::create(&path).unwrap(), &records).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let rows: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|r| r.unwrap().to_string())
+            .collect();
+
+        assert_eq!(2, rows.len());
+        assert!(rows[0].contains("status: \"200 OK\", took_ns: 5000000"));
+        assert!(rows[1].contains("status: \"timeout\", took_ns: 7000000"));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::stats::Stats;
+use std::io;
+
+/// The aggregated results of a single target, written as one series
+/// per metric.
+pub struct Series<'a> {
+    pub method: String,
+    pub url: String,
+    pub stats: &'a Stats,
+    pub throughput: f64,
+}
+
+/// Writes the given series in the Prometheus text exposition format.
+pub fn write(mut w: impl io::Write, series: &[Series]) -> io::Result<()> {
+    writeln!(
+        w,
+        "# HELP rush_request_duration_seconds The latency of the benchmarked requests.\n\
+        # TYPE rush_request_duration_seconds summary"
+    )?;
+    for s in series {
+        let labels = s.labels();
+        for (quantile, v) in [
+            ("0.5", s.stats.median),
+            ("0.9", s.stats.p90),
+            ("0.95", s.stats.p95),
+            ("0.99", s.stats.p99),
+        ] {
+            writeln!(
+                w,
+                "rush_request_duration_seconds{{{labels},quantile=\"{quantile}\"}} {}",
+                v.as_secs_f64()
+            )?;
+        }
+        writeln!(
+            w,
+            "rush_request_duration_seconds_sum{{{labels}}} {}",
+            s.stats.total.as_secs_f64()
+        )?;
+        writeln!(
+            w,
+            "rush_request_duration_seconds_count{{{labels}}} {}",
+            s.stats.count
+        )?;
+    }
+
+    writeln!(
+        w,
+        "# HELP rush_requests_total The amount of sent requests.\n\
+        # TYPE rush_requests_total counter"
+    )?;
+    for s in series {
+        // Failed requests are sent as well, so that the errors never
+        // exceed the total.
+        writeln!(
+            w,
+            "rush_requests_total{{{}}} {}",
+            s.labels(),
+            s.stats.total_count()
+        )?;
+    }
+
+    writeln!(
+        w,
+        "# HELP rush_request_errors_total The amount of requests which did not succeed.\n\
+        # TYPE rush_request_errors_total counter"
+    )?;
+    for s in series {
+        writeln!(
+            w,
+            "rush_request_errors_total{{{}}} {}",
+            s.labels(),
+            s.stats.failed
+        )?;
+    }
+
+    writeln!(
+        w,
+        "# HELP rush_throughput_requests_per_second The achieved amount of requests per second.\n\
+        # TYPE rush_throughput_requests_per_second gauge"
+    )?;
+    for s in series {
+        writeln!(
+            w,
+            "rush_throughput_requests_per_second{{{}}} {}",
+            s.labels(),
+            s.throughput
+        )?;
+    }
+
+    Ok(())
+}
+
+impl Series<'_> {
+    fn labels(&self) -> String {
+        format!(
+            "method=\"{}\",url=\"{}\"",
+            escape(&self.method),
+            escape(&self.url)
+        )
+    }
+}
+
+fn escape(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        request::{Failure, FailureKind, Response},
+        test_util,
+    };
+    use chrono::Utc;
+    use reqwest::{Method, StatusCode};
+    use std::time::Duration;
+
+    #[test]
+    fn escape_label_values() {
+        assert_eq!(r#"a\"b\\c\nd"#, escape("a\"b\\c\nd"));
+    }
+
+    #[test]
+    fn counters() {
+        let res = [
+            test_util::response(10),
+            Response {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                ..test_util::response(30)
+            },
+        ];
+        let failures = [FailureKind::Connect, FailureKind::Timeout].map(|kind| Failure {
+            index: 0,
+            method: Method::GET,
+            kind,
+            message: String::new(),
+            reason: String::new(),
+            took: Duration::ZERO,
+            timestamp: Utc::now(),
+        });
+        let stats = Stats::from_responses(&res, &failures).unwrap();
+
+        let mut out = vec![];
+        let series = Series {
+            method: "GET".into(),
+            url: "http://localhost/".into(),
+            stats: &stats,
+            throughput: 2.0,
+        };
+        write(&mut out, &[series]).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let labels = r#"{method="GET",url="http://localhost/"}"#;
+        for line in [
+            format!("rush_request_duration_seconds_sum{labels} 0.04"),
+            format!("rush_request_duration_seconds_count{labels} 2"),
+            format!("rush_requests_total{labels} 4"),
+            format!("rush_request_errors_total{labels} 3"),
+            format!("rush_throughput_requests_per_second{labels} 2"),
+        ] {
+            assert!(
+                out.lines().any(|l| l == line),
+                "missing '{line}' in:\n{out}"
+            );
+        }
+    }
+}
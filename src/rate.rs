@@ -0,0 +1,193 @@
+use crate::duration::format_duration;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The interval after which the concurrency is adjusted.
+pub const INTERVAL: Duration = Duration::from_secs(1);
+
+/// The share of the target rate which has to be achieved over the whole
+/// run for the target to be considered sustained.
+const SUSTAINED_SHARE: f64 = 0.95;
+
+/// The factor by which the concurrency exceeds the one required by the
+/// average latency, so that slower requests do not lower the rate.
+const HEADROOM: f64 = 1.2;
+
+/// A target rate of requests per second, which is sustained by adjusting
+/// the concurrency between 1 and `max_parallel`, starting at `initial`.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetRps {
+    pub rps: f64,
+    pub initial: usize,
+    pub max_parallel: usize,
+}
+
+/// Adjusts the concurrency once per interval to the one required to
+/// sustain the target rate at the latency of the last interval. By
+/// Little's law, the amount of requests in flight equals the rate times
+/// the average latency.
+pub struct Controller {
+    target: TargetRps,
+    parallel: usize,
+    adjustments: Vec<Adjustment>,
+}
+
+/// The concurrency during a single interval of a run with a target rate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Adjustment {
+    /// The time since the start of the run at the end of the interval.
+    #[serde(with = "crate::duration::nanos")]
+    pub elapsed: Duration,
+    pub parallel: usize,
+    /// The amount of requests per second completed within the interval.
+    pub rate: f64,
+}
+
+impl Controller {
+    pub fn new(target: TargetRps) -> Self {
+        Self {
+            parallel: target.initial.clamp(1, target.max_parallel),
+            target,
+            adjustments: vec![],
+        }
+    }
+
+    /// Returns the concurrency of the current interval.
+    pub fn parallel(&self) -> usize {
+        self.parallel
+    }
+
+    /// Records the requests completed within the last interval of length
+    /// `span` along with their summed latencies and returns the
+    /// concurrency for the next interval. The concurrency is at most
+    /// halved or doubled per interval, so that single outliers do not
+    /// make it oscillate.
+    pub fn adjust(
+        &mut self,
+        elapsed: Duration,
+        span: Duration,
+        completed: usize,
+        busy: Duration,
+    ) -> usize {
+        let rate = completed as f64 / span.as_secs_f64();
+        let mut required = if completed > 0 {
+            let latency = busy.as_secs_f64() / completed as f64;
+            (self.target.rps * latency * HEADROOM).ceil() as usize
+        } else {
+            // No request completed within the interval, so that the
+            // latency is unknown but at least as long as the interval.
+            self.parallel * 2
+        };
+        if rate < self.target.rps * SUSTAINED_SHARE {
+            required = required.max(self.parallel + 1);
+        }
+
+        self.adjustments.push(Adjustment {
+            elapsed,
+            parallel: self.parallel,
+            rate,
+        });
+        self.parallel = required
+            .clamp(self.parallel.div_ceil(2), self.parallel * 2)
+            .clamp(1, self.target.max_parallel);
+        self.parallel
+    }
+
+    /// Returns the report of the run, which achieved `achieved` requests
+    /// per second overall.
+    pub fn report(&self, achieved: f64) -> RateReport {
+        RateReport {
+            target: self.target.rps,
+            achieved,
+            sustained: achieved >= self.target.rps * SUSTAINED_SHARE,
+            adjustments: self.adjustments.clone(),
+        }
+    }
+}
+
+/// The outcome of a run with a target rate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateReport {
+    /// The target amount of requests per second.
+    pub target: f64,
+    /// The amount of requests per second achieved over the whole run.
+    pub achieved: f64,
+    /// Whether at least 95% of the target rate have been achieved.
+    pub sustained: bool,
+    pub adjustments: Vec<Adjustment>,
+}
+
+impl RateReport {
+    pub fn print(&self) {
+        println!(
+            "\nTarget:     {:>10.2}  req/s ({:.2} achieved, {})",
+            self.target,
+            self.achieved,
+            if self.sustained {
+                "sustained"
+            } else {
+                "not sustained"
+            },
+        );
+
+        if self.adjustments.is_empty() {
+            return;
+        }
+
+        println!("\n{:<12}{:>10}  {:>12}", "Elapsed", "Parallel", "Req/s");
+        for a in &self.adjustments {
+            println!(
+                "{:<12}{:>10}  {:>12.2}",
+                format!("{:.1}", format_duration(a.elapsed)),
+                a.parallel,
+                a.rate,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn controller() {
+        let mut c = Controller::new(TargetRps {
+            rps: 100f64,
+            initial: 4,
+            max_parallel: 16,
+        });
+        let secs = Duration::from_secs;
+
+        // 100 requests per second of 10ms each require 100 * 10ms * 1.2,
+        // i.e. 2 requests in flight, which is half of the current 4.
+        assert_eq!(
+            2,
+            c.adjust(secs(1), secs(1), 100, Duration::from_millis(1000))
+        );
+        // The latency rose to 100ms, which requires 12 requests in flight,
+        // but the concurrency is at most doubled.
+        assert_eq!(4, c.adjust(secs(2), secs(1), 20, secs(2)));
+        assert_eq!(8, c.adjust(secs(3), secs(1), 0, Duration::ZERO));
+        // The concurrency is limited by `max_parallel`.
+        assert_eq!(16, c.adjust(secs(4), secs(1), 10, secs(10)));
+        // The rate is below the target even though the latency suggests
+        // enough requests in flight, so that the concurrency is raised.
+        assert_eq!(
+            16,
+            c.adjust(secs(5), secs(1), 50, Duration::from_millis(500))
+        );
+
+        let report = c.report(97f64);
+        assert!(report.sustained);
+        assert_eq!(
+            vec![4, 2, 4, 8, 16],
+            report
+                .adjustments
+                .iter()
+                .map(|a| a.parallel)
+                .collect::<Vec<_>>()
+        );
+        assert!(!c.report(90f64).sustained);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{
+    request::DEFAULT_TIMEOUT,
+    tls::{self, TlsOptions},
+};
+use anyhow::Result;
+use hyper::{client::HttpConnector, Uri};
+use hyper_rustls::HttpsConnector;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, ACCEPT},
+    Request, Response, Url,
+};
+use std::fmt;
+
+/// Sends requests with the paths of the URLs exactly as given, e.g.
+/// '/a/../b', instead of the paths normalized by the url crate, which
+/// reqwest requires. The requests bypass reqwest and are sent via hyper
+/// over HTTP/1.1, so that responses are never decompressed and redirects
+/// are never followed.
+pub struct RawSender {
+    client: hyper::Client<HttpsConnector<HttpConnector>>,
+    /// The headers sent with every request unless the request sets them.
+    headers: HeaderMap,
+    /// The URIs with the raw paths in the order of the URLs.
+    uris: Vec<Uri>,
+}
+
+/// The error of a request sent via a [`RawSender`].
+#[derive(Debug)]
+pub enum RawError {
+    Http(hyper::Error),
+    /// The request has not been answered within its timeout.
+    Timeout,
+    /// The request has a streamed body, which cannot be sent raw.
+    StreamedBody,
+}
+
+impl fmt::Display for RawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(err) => err.fmt(f),
+            Self::Timeout => f.write_str("operation timed out"),
+            Self::StreamedBody => f.write_str("streamed bodies cannot be sent with a raw path"),
+        }
+    }
+}
+
+impl std::error::Error for RawError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl RawSender {
+    /// Creates a sender for the given URLs, each given as the original
+    /// string along with its parsed URL.
+    pub fn new(urls: &[(&str, &Url)], mut headers: HeaderMap, tls: TlsOptions) -> Result<Self> {
+        // reqwest sends this header by default, so that servers see the
+        // same requests either way.
+        headers
+            .entry(ACCEPT)
+            .or_insert(HeaderValue::from_static("*/*"));
+
+        let uris = urls
+            .iter()
+            .map(|&(raw, url)| raw_uri(raw, url))
+            .collect::<Result<_>>()?;
+
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_tls_config(tls::client_config(&tls)?)
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Ok(Self {
+            client: hyper::Client::builder().build(connector),
+            headers,
+            uris,
+        })
+    }
+
+    /// Sends the request with the dispatch index `i` to the raw path of
+    /// its URL, which is picked cyclically like the URL of the request.
+    /// The timeout of the request, [`DEFAULT_TIMEOUT`] if it has none,
+    /// only applies until the response headers are received.
+    pub async fn send(&self, i: usize, req: Request) -> Result<Response, RawError> {
+        let uri = self.uris[i % self.uris.len()].clone();
+        let body = match req.body() {
+            Some(body) => body.as_bytes().ok_or(RawError::StreamedBody)?.to_vec(),
+            None => vec![],
+        };
+
+        let mut raw = hyper::Request::builder()
+            .method(req.method())
+            .uri(uri)
+            .body(hyper::Body::from(body))
+            .expect("the parts of a valid request form a valid request");
+        let headers = raw.headers_mut();
+        headers.extend(self.headers.clone());
+        for (name, value) in req.headers() {
+            headers.insert(name, value.clone());
+        }
+
+        let timeout = req.timeout().copied().unwrap_or(DEFAULT_TIMEOUT);
+        let res = tokio::time::timeout(timeout, self.client.request(raw))
+            .await
+            .map_err(|_| RawError::Timeout)?;
+        Ok(res.map_err(RawError::Http)?.into())
+    }
+}
+
+/// Returns the URI of the parsed URL with the path and the query exactly
+/// as given in the original string; the fragment is omitted.
+fn raw_uri(raw: &str, url: &Url) -> Result<Uri> {
+    let rest = raw.split_once("://").map_or(raw, |(_, rest)| rest);
+    let rest = rest.split('#').next().unwrap_or_default();
+    let path = match rest.find(['/', '?']) {
+        Some(i) if rest[i..].starts_with('?') => format!("/{}", &rest[i..]),
+        Some(i) => rest[i..].to_string(),
+        None => "/".to_string(),
+    };
+
+    let host = url.host_str().unwrap_or_default();
+    let authority = match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    };
+    format!("{}://{authority}{path}", url.scheme())
+        .parse()
+        .map_err(|err| anyhow::anyhow!("invalid raw path '{path}' of '{raw}': {err}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn raw_paths() {
+        let uri = |raw: &str| {
+            let url: Url = raw.parse().unwrap();
+            raw_uri(raw, &url).unwrap().to_string()
+        };
+
+        assert_eq!("http://localhost/a/../b", uri("http://localhost/a/../b"));
+        assert_eq!(
+            "http://localhost:8080//a/./b",
+            uri("http://localhost:8080//a/./b")
+        );
+        assert_eq!(
+            "http://localhost/%2e%2e/a?x=%2F",
+            uri("http://localhost/%2e%2e/a?x=%2F")
+        );
+        assert_eq!("http://localhost/?x=1", uri("http://localhost?x=1#frag"));
+        assert_eq!("https://[::1]/", uri("https://[::1]"));
+
+        let raw = "http://localhost/a b";
+        assert!(raw_uri(raw, &raw.parse().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::{fs, path::Path, time::Duration};
+
+/// Reads the timestamps of the requests from a CSV file written via
+/// `csv` or `output` and returns the offsets at which they have been sent
+/// relative to the earliest request, in ascending order.
+pub fn read_schedule(path: impl AsRef<Path>) -> Result<Vec<Duration>> {
+    let mut timestamps = fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(n, l)| {
+            let field = l.split(',').next().unwrap_or_default();
+            parse_timestamp(field)
+                .ok_or_else(|| anyhow::anyhow!("invalid timestamp in line {}: '{field}'", n + 1))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if timestamps.is_empty() {
+        anyhow::bail!("the CSV file does not contain any requests");
+    }
+
+    timestamps.sort();
+    let first = timestamps[0];
+    Ok(timestamps
+        .into_iter()
+        .map(|t| (t - first).to_std().unwrap_or_default())
+        .collect())
+}
+
+/// Parses a timestamp as written to the CSV output, e.g.
+/// '2024-01-01 12:00:00.123456789 UTC'.
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    let naive = s.strip_suffix(" UTC").unwrap_or(s);
+    NaiveDateTime::parse_from_str(naive, "%Y-%m-%d %H:%M:%S%.f")
+        .map(|t| t.and_utc())
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn schedule() {
+        let started = Utc::now();
+        let csv = [250, 0, 1000]
+            .map(|ms| {
+                format!(
+                    "{},200 OK,1000\n",
+                    started + chrono::Duration::milliseconds(ms)
+                )
+            })
+            .concat();
+
+        let path = test_util::temp_dir("replay").join("schedule.csv");
+        fs::write(&path, csv + "\n").unwrap();
+        assert_eq!(
+            vec![0, 250, 1000]
+                .into_iter()
+                .map(Duration::from_millis)
+                .collect::<Vec<_>>(),
+            read_schedule(&path).unwrap()
+        );
+
+        fs::write(&path, "yesterday,200 OK,1000\n").unwrap();
+        assert!(read_schedule(&path).is_err());
+    }
+}
@@ -1,75 +1,1263 @@
+use crate::{
+    body::Body,
+    duration::{parse_header_duration, DurationRange},
+    raw::{RawError, RawSender},
+    tls::{counting_config, HandshakeCounter, Handshakes, Resumption, TlsOptions},
+    token::TokenSource,
+    urls::Sticky,
+};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use hyper::client::connect::dns::Name;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use reqwest::{
-    blocking::Request,
-    header::{HeaderMap, HeaderName},
-    Method, StatusCode, Url,
+    dns::{Addrs, Resolve, Resolving},
+    header::{
+        HeaderMap, HeaderName, HeaderValue, ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING, ETAG,
+        HOST, IF_NONE_MATCH,
+    },
+    redirect, tls, Method, Request, StatusCode, Url, Version,
+};
+use std::{
+    cell::RefCell,
+    collections::{
+        hash_map::{DefaultHasher, Entry},
+        HashMap,
+    },
+    error::Error,
+    fmt,
+    hash::{Hash, Hasher},
+    io,
+    net::ToSocketAddrs,
+    num::NonZeroUsize,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
 };
-use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub struct Response {
+    /// The dispatch index of the request.
+    pub index: usize,
+    pub method: Method,
     pub status: StatusCode,
     pub took: Duration,
     pub timestamp: DateTime<Utc>,
+    /// Whether the response body equals the golden body, if set.
+    pub golden_match: Option<bool>,
+    /// The duration of the explicit DNS lookup preceding the request,
+    /// which is not included in `took`.
+    pub dns: Option<Duration>,
+    /// Whether the response has been marked as failed by the error
+    /// injection although the request succeeded.
+    pub injected: bool,
+    /// The values of the captured response headers in the order they
+    /// have been configured in; unset if the header was missing.
+    pub captured: Vec<Option<String>>,
+    /// The processing time reported by the server in the configured
+    /// header, if present and valid.
+    pub server_time: Option<Duration>,
+    /// The size of the response body in bytes, if it has been read; the
+    /// size on the wire if decompression is disabled.
+    pub body_size: Option<u64>,
+    /// Whether reading the response body has been stopped after
+    /// `max_response_bytes`, so that `body_size` is the amount read.
+    pub truncated: bool,
+    /// The size of the request body in bytes, if a body has been sent.
+    pub sent_size: Option<u64>,
+    /// The Content-Encoding of the response, which is only kept if
+    /// decompression is disabled.
+    pub content_encoding: Option<String>,
+    /// The time until the response headers have been received, which
+    /// includes establishing the connection, if required.
+    pub ttfb: Option<Duration>,
+    /// The time until the response body has been received completely,
+    /// if the body has been read.
+    pub ttlb: Option<Duration>,
+    /// The negotiated HTTP version.
+    pub version: Version,
+    /// Whether the request has been sent with the ETag of a previous
+    /// response in the If-None-Match header.
+    pub conditional: bool,
+    /// The responses of the redirects followed and the final response,
+    /// if redirects are traced and the request has been redirected.
+    pub redirects: Vec<Hop>,
+}
+
+/// A response received while following the redirects of a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hop {
+    pub status: StatusCode,
+    pub url: Url,
+}
+
+impl Response {
+    /// Returns true if the request has not been answered with a
+    /// client or server error status and no error has been injected.
+    pub fn is_success(&self) -> bool {
+        !self.injected && !self.status.is_client_error() && !self.status.is_server_error()
+    }
+
+    /// Returns the status, labeled if an error has been injected or the
+    /// body has been truncated.
+    pub fn status_label(&self) -> String {
+        match (self.injected, self.truncated) {
+            (true, _) => format!("{} (injected)", self.status),
+            (_, true) => format!("{} (truncated)", self.status),
+            _ => self.status.to_string(),
+        }
+    }
+
+    /// Returns the time spent reading the body after the headers have
+    /// been received, if the body has been read.
+    pub fn transfer(&self) -> Option<Duration> {
+        Some(self.ttlb?.saturating_sub(self.ttfb?))
+    }
+}
+
+/// A request which could not be completed.
+#[derive(Debug)]
+pub struct Failure {
+    /// The dispatch index of the request.
+    pub index: usize,
+    pub method: Method,
+    pub kind: FailureKind,
+    pub message: String,
+    /// The innermost cause of the failure without the URL, by which the
+    /// failures of requests to different URLs are grouped.
+    pub reason: String,
+    pub took: Duration,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The kind of transport error a request failed with, i.e. without a
+/// response; responses with an error status are not failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FailureKind {
+    Dns,
+    /// The connection could not be established, e.g. it was refused.
+    Connect,
+    /// The TLS handshake of the connection failed.
+    Tls,
+    Timeout,
+    Other,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dns => "dns",
+            Self::Connect => "connect",
+            Self::Tls => "tls",
+            Self::Timeout => "timeout",
+            Self::Other => "error",
+        }
+    }
+}
+
+impl From<&SendError> for FailureKind {
+    fn from(err: &SendError) -> Self {
+        match err {
+            SendError::Reqwest(err) if err.is_timeout() => Self::Timeout,
+            SendError::Raw(RawError::Timeout) => Self::Timeout,
+            err if err.is_connect() && err.is_caused_by::<DnsError>() => Self::Dns,
+            err if err.is_connect() && err.is_caused_by::<rustls::Error>() => Self::Tls,
+            err if err.is_connect() => Self::Connect,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// The error of a request sent either via reqwest or, if raw paths are
+/// sent, via a [`RawSender`].
+#[derive(Debug)]
+enum SendError {
+    Reqwest(reqwest::Error),
+    Raw(RawError),
+}
+
+impl SendError {
+    fn is_connect(&self) -> bool {
+        match self {
+            Self::Reqwest(err) => err.is_connect(),
+            Self::Raw(RawError::Http(err)) => err.is_connect(),
+            Self::Raw(_) => false,
+        }
+    }
+
+    /// Returns the innermost error of the chain of sources.
+    fn root_cause(&self) -> &(dyn Error + 'static) {
+        let mut cause: &(dyn Error + 'static) = self;
+        while let Some(source) = cause.source() {
+            cause = source;
+        }
+        cause
+    }
+
+    /// Returns the message of the root cause without the URL, which is
+    /// only part of it if the error has no sources.
+    fn reason(&self) -> String {
+        let reason = self.root_cause().to_string();
+        match self {
+            Self::Reqwest(err) => match err.url() {
+                Some(url) => reason.replace(&format!(" for url ({url})"), ""),
+                None => reason,
+            },
+            Self::Raw(_) => reason,
+        }
+    }
+
+    /// Whether the error has been caused by an error of the given type,
+    /// which may be wrapped in I/O errors, e.g. the TLS errors of rustls.
+    fn is_caused_by<E: Error + 'static>(&self) -> bool {
+        let mut cause: Option<&(dyn Error + 'static)> = self.source();
+        while let Some(err) = cause {
+            if err.is::<E>() {
+                return true;
+            }
+            // The source of an I/O error skips the error it wraps.
+            cause = match err.downcast_ref::<io::Error>().and_then(io::Error::get_ref) {
+                Some(inner) => Some(inner),
+                None => err.source(),
+            };
+        }
+        false
+    }
+}
+
+impl From<reqwest::Error> for SendError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Reqwest(err)
+    }
+}
+
+impl From<RawError> for SendError {
+    fn from(err: RawError) -> Self {
+        Self::Raw(err)
+    }
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reqwest(err) => err.fmt(f),
+            Self::Raw(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for SendError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Reqwest(err) => err.source(),
+            Self::Raw(err) => err.source(),
+        }
+    }
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A TLS version the connections can be pinned to; older versions are
+/// not supported by the TLS implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsVersion {
+    Tls12,
+    Tls13,
+}
+
+impl TlsVersion {
+    fn as_reqwest(self) -> tls::Version {
+        match self {
+            Self::Tls12 => tls::Version::TLS_1_2,
+            Self::Tls13 => tls::Version::TLS_1_3,
+        }
+    }
+}
+
+impl FromStr for TlsVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1.2" => Ok(Self::Tls12),
+            "1.3" => Ok(Self::Tls13),
+            _ => anyhow::bail!("unsupported TLS version: '{s}'; expected '1.2' or '1.3'"),
+        }
+    }
+}
+
+impl fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Tls12 => "TLS 1.2",
+            Self::Tls13 => "TLS 1.3",
+        })
+    }
+}
+
+/// The configuration used to build a [`Client`].
+#[derive(Debug, Default)]
+pub struct ClientConfig {
+    /// The URLs requested cyclically for consecutive requests.
+    pub urls: Vec<String>,
+    /// The scheme, e.g. 'https', prepended to URLs without a scheme;
+    /// such URLs are rejected if unset.
+    pub default_scheme: Option<String>,
+    /// The HTTP methods used cyclically for consecutive requests.
+    pub methods: Vec<String>,
+    /// The request bodies used cyclically for consecutive requests;
+    /// no body is sent if empty.
+    pub bodies: Vec<Body>,
+    /// The bodies sent with the requests of the given methods instead
+    /// of `bodies`.
+    pub method_bodies: Vec<(String, Body)>,
+    /// Headers in the format 'key: value'.
+    pub headers: Vec<String>,
+    pub accept_invalid_certs: bool,
+    /// The oldest TLS version accepted for connections.
+    pub tls_min_version: Option<TlsVersion>,
+    /// The newest TLS version offered for connections.
+    pub tls_max_version: Option<TlsVersion>,
+    /// The name of the query parameter used to bust caches.
+    pub cache_bust: Option<String>,
+    pub drain_body: bool,
+    /// The timeout of each request pending until it has been completed;
+    /// defaults to [`DEFAULT_TIMEOUT`].
+    pub timeout: Option<DurationRange>,
+    /// The body each response is expected to match.
+    pub golden: Option<Vec<u8>>,
+    /// Resolves the host explicitly before each request.
+    pub measure_dns: bool,
+    /// The value of the Host header, which overrides the one derived
+    /// from the URL.
+    pub host: Option<String>,
+    /// Streams the bodies with chunked transfer encoding instead of
+    /// sending a Content-Length.
+    pub chunked: bool,
+    /// Fails requests whose body is rendered per request but equals the
+    /// body of a request with another dispatch index, which guards
+    /// against rendered bodies being reused. Requires bodies with a
+    /// Content-Length, since streamed bodies cannot be inspected.
+    pub no_body_reuse: bool,
+    /// The source of the bearer token sent with each request, which is
+    /// refreshed when a request is rejected with 401.
+    pub token: Option<Arc<TokenSource>>,
+    /// The percentage of successful responses marked as failed.
+    pub inject_errors: Option<f64>,
+    /// The seed deciding which responses are marked as failed.
+    pub seed: u64,
+    /// The names of the response headers whose values are captured.
+    pub capture_headers: Vec<String>,
+    /// The name of the response header the server reports its
+    /// processing time in.
+    pub server_time_header: Option<String>,
+    /// Disables the transparent decompression of gzip, brotli and
+    /// deflate encoded responses, so that the raw bodies are read.
+    pub no_decompress: bool,
+    /// The amount of bytes after which reading a response body is
+    /// stopped and the response is marked as truncated.
+    pub max_response_bytes: Option<u64>,
+    /// The value of the Accept-Encoding header, which defaults to the
+    /// encodings which are decompressed; [`NO_ENCODING`] omits the
+    /// header and disables the decompression.
+    pub accept_encoding: Option<String>,
+    /// Sends the paths of the URLs exactly as given instead of the paths
+    /// normalized by the url crate; see [`RawSender`].
+    pub raw_path: bool,
+    /// Sends the ETag of the last response which had one in the
+    /// If-None-Match header of subsequent requests. The ETag is shared
+    /// by all URLs, so that this is meant for a single URL.
+    pub conditional: bool,
+    /// Sends each request over a new connection whose TLS handshake is a
+    /// full one or resumes a previous session; see [`Resumption`].
+    pub tls_resumption: Option<Resumption>,
+    /// The amount of simulated users whose requests are routed to the
+    /// same URL each; see [`Sticky`].
+    pub sticky: Option<NonZeroUsize>,
+    /// The name of the header the key of the user of each request is
+    /// sent in, if the requests are routed per user.
+    pub sticky_header: Option<String>,
+    /// Speaks HTTP/2 without negotiating it, so that all concurrent
+    /// requests to a host are multiplexed over a single connection.
+    pub multiplex: bool,
+    /// Records the redirects followed by each request.
+    pub trace_redirects: bool,
+    /// Signs each request with the AWS Signature Version 4.
+    #[cfg(feature = "aws-sigv4")]
+    pub aws_sigv4: Option<Arc<crate::sigv4::Signer>>,
+    /// Speaks HTTP/3 over QUIC without negotiating it.
+    #[cfg(feature = "http3")]
+    pub http3: bool,
+}
+
+/// Parts of the errors of failed TLS handshakes which indicate that the
+/// client and the server do not share a TLS version.
+const TLS_VERSION_ERRORS: &[&str] = &["ProtocolVersion", "HandshakeFailure", "incompatible"];
+
+/// The `accept_encoding` value which omits the Accept-Encoding header.
+pub const NO_ENCODING: &str = "none";
+
+/// The encodings reqwest decompresses transparently.
+const DECOMPRESSED_ENCODINGS: &str = "gzip, br, deflate";
+
+/// The resolution of the monotonic clock above which latencies are
+/// measured too coarsely, e.g. against local servers.
+pub const COARSE_CLOCK: Duration = Duration::from_micros(1);
+
+/// The amount of ticks of the monotonic clock observed to probe its
+/// resolution.
+const CLOCK_TICKS: usize = 100;
+
+/// The amount of redirects followed per request, like the default
+/// policy of reqwest.
+const MAX_REDIRECTS: usize = 10;
+
+/// The timeout of requests if none is configured, which the blocking
+/// client of reqwest applied implicitly.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+tokio::task_local! {
+    /// The redirects followed by the request sent by the current task,
+    /// which the redirect policy records them in if they are traced.
+    static REDIRECTS: RefCell<Vec<Hop>>;
 }
 
 pub struct Client {
-    client: reqwest::blocking::Client,
-    url: Url,
-    method: Method,
-    body: Option<Vec<u8>>,
+    client: reqwest::Client,
+    tls_min_version: Option<TlsVersion>,
+    tls_max_version: Option<TlsVersion>,
+    urls: Vec<Url>,
+    methods: Vec<Method>,
+    bodies: Vec<Body>,
+    method_bodies: HashMap<Method, Body>,
+    cache_bust: Option<String>,
+    drain_body: bool,
+    max_response_bytes: Option<u64>,
+    timeout: Option<DurationRange>,
+    golden: Option<Vec<u8>>,
+    golden_mismatch: OnceLock<Vec<u8>>,
+    measure_dns: bool,
+    host: Option<HeaderValue>,
+    chunked: bool,
+    /// The hashes of the rendered bodies sent so far along with the
+    /// dispatch index of their request, if reused bodies are rejected.
+    body_hashes: Option<Mutex<HashMap<u64, usize>>>,
+    token: Option<Arc<TokenSource>>,
+    resolver: Arc<CountingResolver>,
+    inject_errors: Option<f64>,
+    seed: u64,
+    capture_headers: Vec<HeaderName>,
+    server_time_header: Option<HeaderName>,
+    /// Sends the requests instead of `client` if raw paths are sent.
+    raw: Option<RawSender>,
+    /// The ETag of the last response which had one, if conditional
+    /// requests are sent.
+    etag: Option<Mutex<Option<HeaderValue>>>,
+    /// Counts the TLS handshakes if their resumption is controlled.
+    handshakes: Option<Arc<HandshakeCounter>>,
+    sticky: Option<Sticky>,
+    sticky_header: Option<HeaderName>,
+    trace_redirects: bool,
+    #[cfg(feature = "aws-sigv4")]
+    aws_sigv4: Option<Arc<crate::sigv4::Signer>>,
+}
+
+/// The parts of a response kept after it has been received.
+struct Received {
+    status: StatusCode,
+    golden_match: Option<bool>,
+    captured: Vec<Option<String>>,
+    server_time: Option<Duration>,
+    body_size: Option<u64>,
+    truncated: bool,
+    content_encoding: Option<String>,
+    ttfb: Duration,
+    ttlb: Option<Duration>,
+    version: Version,
+    conditional: bool,
+    redirects: Vec<Hop>,
 }
 
 impl Client {
-    pub fn new(
-        url: &str,
-        method: &str,
-        body: Option<Vec<u8>>,
-        headers: &[String],
-        accept_invalid_certs: bool,
-    ) -> Result<Self> {
-        let client = reqwest::blocking::Client::builder()
-            .default_headers(into_header_map(headers)?)
-            .danger_accept_invalid_certs(accept_invalid_certs)
-            .build()?;
-
-        let url = url.parse()?;
-        let method = method.parse()?;
+    pub fn new(cfg: ClientConfig) -> Result<Self> {
+        let resolver = Arc::new(CountingResolver::default());
+        let mut headers = into_header_map(&cfg.headers)?;
+
+        // reqwest only sends an Accept-Encoding header for the encodings
+        // it decompresses, so that it is sent explicitly when either is
+        // configured separately.
+        let accept_encoding = match cfg.accept_encoding.as_deref() {
+            Some(NO_ENCODING) => None,
+            Some(v) => Some(v),
+            None if cfg.no_decompress => Some(DECOMPRESSED_ENCODINGS),
+            None => None,
+        };
+        if let Some(v) = accept_encoding {
+            headers.insert(ACCEPT_ENCODING, v.parse()?);
+        }
+
+        let default_headers = cfg.raw_path.then(|| headers.clone());
+        let mut builder = reqwest::Client::builder()
+            .dns_resolver(resolver.clone())
+            .default_headers(headers)
+            .danger_accept_invalid_certs(cfg.accept_invalid_certs);
+        if let (Some(min), Some(max)) = (cfg.tls_min_version, cfg.tls_max_version) {
+            if min > max {
+                anyhow::bail!("the minimum TLS version {min} exceeds the maximum version {max}");
+            }
+        }
+        if let Some(v) = cfg.tls_min_version {
+            builder = builder.min_tls_version(v.as_reqwest());
+        }
+        if let Some(v) = cfg.tls_max_version {
+            builder = builder.max_tls_version(v.as_reqwest());
+        }
+        if cfg.no_decompress || cfg.accept_encoding.as_deref() == Some(NO_ENCODING) {
+            builder = builder.no_gzip().no_brotli().no_deflate();
+        }
+        let tls = TlsOptions {
+            accept_invalid_certs: cfg.accept_invalid_certs,
+            min_version: cfg.tls_min_version,
+            max_version: cfg.tls_max_version,
+        };
+        let handshakes = cfg
+            .tls_resumption
+            .map(|_| Arc::<HandshakeCounter>::default());
+        if let (Some(resumption), Some(counter)) = (cfg.tls_resumption, &handshakes) {
+            // The preconfigured TLS settings replace the ones above.
+            builder = builder
+                .use_preconfigured_tls(counting_config(&tls, resumption, counter.clone())?)
+                .pool_max_idle_per_host(0);
+        }
+        if cfg.multiplex {
+            // Unlike a negotiated protocol, the protocol known in advance
+            // lets concurrent requests wait for the connection being
+            // established instead of opening their own.
+            builder = builder.http2_prior_knowledge();
+        }
+        #[cfg(feature = "http3")]
+        if cfg.http3 {
+            builder = builder.http3_prior_knowledge();
+        }
+        if cfg.trace_redirects {
+            builder = builder.redirect(redirect::Policy::custom(trace_redirect));
+        }
+        let client = builder.build()?;
+
+        let urls = cfg
+            .urls
+            .iter()
+            .map(|u| parse_url(u, cfg.default_scheme.as_deref()))
+            .collect::<Result<Vec<_>>>()?;
+
+        if urls.is_empty() {
+            anyhow::bail!("no URL given");
+        }
+        if let Some(u) = urls.iter().find(|u| u.scheme() != "https") {
+            if cfg.tls_resumption.is_some() {
+                anyhow::bail!("TLS resumption is controlled for HTTPS URLs only: '{u}'");
+            }
+        }
+
+        let raw = match default_headers {
+            Some(headers) => {
+                // A signature would cover the normalized path instead.
+                #[cfg(feature = "aws-sigv4")]
+                if cfg.aws_sigv4.is_some() {
+                    anyhow::bail!("raw paths cannot be signed with the AWS Signature Version 4");
+                }
+                let urls: Vec<_> = cfg.urls.iter().map(String::as_str).zip(&urls).collect();
+                Some(RawSender::new(&urls, headers, tls)?)
+            }
+            None => None,
+        };
+
+        let methods = cfg
+            .methods
+            .iter()
+            .map(|m| {
+                m.parse()
+                    .map_err(|_| anyhow::anyhow!("invalid HTTP method: '{m}'"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if methods.is_empty() {
+            anyhow::bail!("no HTTP method given");
+        }
+
+        let method_bodies = cfg
+            .method_bodies
+            .into_iter()
+            .map(|(m, body)| {
+                let method: Method = m
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("invalid HTTP method: '{m}'"))?;
+                Ok((method, body))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        let capture_headers = cfg
+            .capture_headers
+            .iter()
+            .map(|h| parse_header_name(h))
+            .collect::<Result<Vec<_>>>()?;
+        let server_time_header = cfg
+            .server_time_header
+            .as_deref()
+            .map(parse_header_name)
+            .transpose()?;
+
+        let sticky = cfg.sticky.map(|users| Sticky::new(users, &urls));
 
         Ok(Self {
             client,
-            url,
-            method,
-            body,
+            tls_min_version: cfg.tls_min_version,
+            tls_max_version: cfg.tls_max_version,
+            urls,
+            methods,
+            bodies: cfg.bodies,
+            method_bodies,
+            cache_bust: cfg.cache_bust,
+            drain_body: cfg.drain_body,
+            max_response_bytes: cfg.max_response_bytes,
+            timeout: cfg.timeout,
+            golden: cfg.golden,
+            golden_mismatch: OnceLock::new(),
+            measure_dns: cfg.measure_dns,
+            host: cfg.host.map(|h| h.parse()).transpose()?,
+            chunked: cfg.chunked,
+            body_hashes: cfg.no_body_reuse.then(Mutex::default),
+            token: cfg.token,
+            resolver,
+            inject_errors: cfg.inject_errors,
+            seed: cfg.seed,
+            capture_headers,
+            server_time_header,
+            raw,
+            etag: cfg.conditional.then(Mutex::default),
+            handshakes,
+            sticky,
+            sticky_header: cfg
+                .sticky_header
+                .as_deref()
+                .map(parse_header_name)
+                .transpose()?,
+            trace_redirects: cfg.trace_redirects,
+            #[cfg(feature = "aws-sigv4")]
+            aws_sigv4: cfg.aws_sigv4,
         })
     }
 
-    pub fn send(&self) -> Result<Response> {
-        let req = self.create_request();
+    pub fn urls(&self) -> &[Url] {
+        &self.urls
+    }
+
+    pub fn methods(&self) -> &[Method] {
+        &self.methods
+    }
+
+    pub fn golden(&self) -> Option<&[u8]> {
+        self.golden.as_deref()
+    }
+
+    /// Returns how the request bodies are framed, if any are sent.
+    pub fn body_encoding(&self) -> Option<&'static str> {
+        match (
+            self.bodies.is_empty() && self.method_bodies.is_empty(),
+            self.chunked,
+        ) {
+            (true, _) => None,
+            (false, true) => Some("chunked"),
+            (false, false) => Some("content-length"),
+        }
+    }
+
+    /// Returns the amount of connections established so far. Returns
+    /// `None` if the host of any URL is an IP address, because new
+    /// connections are only detected by their host lookups, or if raw
+    /// paths are sent, which bypasses the lookups.
+    pub fn connections(&self) -> Option<usize> {
+        (self.raw.is_none() && self.urls.iter().all(|u| u.domain().is_some()))
+            .then(|| self.resolver.lookups.load(Ordering::Relaxed))
+    }
+
+    /// Returns the routing of the requests per user, if set.
+    pub fn sticky(&self) -> Option<&Sticky> {
+        self.sticky.as_ref()
+    }
+
+    /// Returns the TLS handshakes performed so far if their resumption is
+    /// controlled, which is the only case in which they are counted.
+    pub fn handshakes(&self) -> Option<Handshakes> {
+        self.handshakes.as_ref().map(|c| c.get())
+    }
+
+    /// Returns whether the response bodies are read, so that their sizes
+    /// are known.
+    pub fn reads_body(&self) -> bool {
+        self.drain_body || self.golden.is_some()
+    }
+
+    /// Returns the first response body which did not match the golden
+    /// body, if any.
+    pub fn golden_mismatch(&self) -> Option<&[u8]> {
+        self.golden_mismatch.get().map(Vec::as_slice)
+    }
+
+    /// Sends the request with the given dispatch index `i`.
+    pub async fn send(&self, i: usize) -> Result<Response, Failure> {
+        self.send_with(i, None).await
+    }
+
+    /// Sends the request with the given dispatch index `i` and aborts it
+    /// with a timeout failure if it has not been completed at `stop`.
+    pub async fn send_before(&self, i: usize, stop: Instant) -> Result<Response, Failure> {
+        self.send_with(i, Some(stop)).await
+    }
+
+    async fn send_with(&self, i: usize, stop: Option<Instant>) -> Result<Response, Failure> {
+        let (mut req, sent_size) = self.create_request(i);
+        cap_timeout(&mut req, stop);
+        let method = req.method().clone();
+
+        if let Err(message) = self.verify_fresh_body(i, &req) {
+            return Err(Failure {
+                index: i,
+                method,
+                kind: FailureKind::Other,
+                message,
+                reason: "the body equals the one of another request, although it is rendered per \
+                    request"
+                    .into(),
+                took: Duration::ZERO,
+                timestamp: Utc::now(),
+            });
+        }
+
+        let dns = if self.measure_dns {
+            let started = Utc::now();
+            let before = Instant::now();
+            match self.resolve(i).await {
+                Ok(()) => Some(before.elapsed()),
+                Err(err) => {
+                    return Err(Failure {
+                        index: i,
+                        method,
+                        kind: FailureKind::Dns,
+                        message: err.to_string(),
+                        reason: err.to_string(),
+                        took: before.elapsed(),
+                        timestamp: started,
+                    })
+                }
+            }
+        } else {
+            None
+        };
+
+        let generation = self.authorize(&mut req);
 
         let started = Utc::now();
-        let before = Instant::now();
-        let res = self.client.execute(req)?;
+        let mut before = Instant::now();
+        let mut res = self.execute(i, req).await;
+
+        // Retry once with a fresh token if the current one has been
+        // rejected; only the retried request is measured.
+        if let (
+            Some(token),
+            Some(generation),
+            Ok(Received {
+                status: StatusCode::UNAUTHORIZED,
+                ..
+            }),
+        ) = (&self.token, generation, &res)
+        {
+            // The token command is executed outside of the runtime, so
+            // that it does not block other requests.
+            let refreshed = {
+                let token = token.clone();
+                tokio::task::spawn_blocking(move || token.refresh(generation)).await
+            };
+            if let Err(err) = refreshed.map_err(anyhow::Error::from).and_then(|r| r) {
+                return Err(Failure {
+                    index: i,
+                    method,
+                    kind: FailureKind::Other,
+                    message: format!("failed to refresh the token: {err}"),
+                    reason: format!("failed to refresh the token: {err}"),
+                    took: before.elapsed(),
+                    timestamp: started,
+                });
+            }
+
+            let (mut req, _) = self.create_request(i);
+            cap_timeout(&mut req, stop);
+            self.authorize(&mut req);
+            before = Instant::now();
+            res = self.execute(i, req).await;
+        }
         let after = Instant::now();
 
-        Ok(Response {
-            status: res.status(),
-            took: after - before,
-            timestamp: started,
+        match res {
+            Ok(received) => Ok(Response {
+                index: i,
+                method,
+                status: received.status,
+                took: after - before,
+                timestamp: started,
+                golden_match: received.golden_match,
+                dns,
+                injected: self.inject_error(i),
+                captured: received.captured,
+                server_time: received.server_time,
+                body_size: received.body_size,
+                truncated: received.truncated,
+                sent_size,
+                content_encoding: received.content_encoding,
+                ttfb: Some(received.ttfb),
+                ttlb: received.ttlb,
+                version: received.version,
+                conditional: received.conditional,
+                redirects: received.redirects,
+            }),
+            Err(err) => Err(Failure {
+                index: i,
+                method,
+                kind: (&err).into(),
+                message: self.failure_message(&err),
+                reason: err.reason(),
+                took: after - before,
+                timestamp: started,
+            }),
+        }
+    }
+
+    /// Returns the message of the failed request, which points out the
+    /// pinned TLS versions if the server rejected them during the
+    /// handshake.
+    fn failure_message(&self, err: &SendError) -> String {
+        if self.tls_min_version.is_none() && self.tls_max_version.is_none() {
+            return err.to_string();
+        }
+
+        // The messages of the outer errors already include the root cause.
+        let cause = err.root_cause().to_string();
+        if !err.is_connect() || !TLS_VERSION_ERRORS.iter().any(|e| cause.contains(e)) {
+            return err.to_string();
+        }
+
+        let min = self.tls_min_version.unwrap_or(TlsVersion::Tls12);
+        let max = self.tls_max_version.unwrap_or(TlsVersion::Tls13);
+        let pinned = if min == max {
+            min.to_string()
+        } else {
+            format!("{min} to {max}")
+        };
+        format!(
+            "the TLS handshake failed, probably because the server does not support \
+            {pinned}: {cause}"
+        )
+    }
+
+    /// Sets the Authorization header to the current token, if any, and
+    /// returns the generation of the token.
+    fn authorize(&self, req: &mut Request) -> Option<u64> {
+        let (value, generation) = self.token.as_ref()?.current();
+        req.headers_mut().insert(AUTHORIZATION, value);
+        Some(generation)
+    }
+
+    /// Decides whether an error is injected into the response of the
+    /// request with the dispatch index `i`. The decision only depends on
+    /// the seed and the index, so that it is independent of the order in
+    /// which the requests complete.
+    fn inject_error(&self, i: usize) -> bool {
+        let Some(pct) = self.inject_errors else {
+            return false;
+        };
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(i as u64));
+        rng.gen_bool(pct / 100f64)
+    }
+
+    async fn resolve(&self, i: usize) -> io::Result<()> {
+        let url = self.url(i);
+        let host = url.host_str().unwrap_or_default().to_string();
+        let port = url.port_or_known_default().unwrap_or_default();
+        tokio::task::spawn_blocking(move || {
+            (host.as_str(), port)
+                .to_socket_addrs()?
+                .next()
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("no address found for {host}"),
+                    )
+                })
         })
+        .await??;
+        Ok(())
     }
 
-    fn create_request(&self) -> Request {
-        let mut req = Request::new(self.method.clone(), self.url.clone());
-        if let Some(body) = self.body.clone() {
-            *req.body_mut() = Some(body.into());
+    async fn execute(&self, i: usize, req: Request) -> Result<Received, SendError> {
+        let conditional = req.headers().contains_key(IF_NONE_MATCH);
+        let before = Instant::now();
+        let mut redirects = vec![];
+        let mut res = match &self.raw {
+            Some(raw) => raw.send(i, req).await?,
+            None if self.trace_redirects => {
+                let (res, hops) = REDIRECTS
+                    .scope(RefCell::default(), async {
+                        let res = self.client.execute(req).await;
+                        (res, REDIRECTS.with(RefCell::take))
+                    })
+                    .await;
+                let res = res?;
+                if !hops.is_empty() {
+                    redirects = hops;
+                    redirects.push(Hop {
+                        status: res.status(),
+                        url: res.url().clone(),
+                    });
+                }
+                res
+            }
+            None => self.client.execute(req).await?,
+        };
+        let ttfb = before.elapsed();
+        let header = |name| {
+            res.headers()
+                .get(name)
+                .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+        };
+        let mut received = Received {
+            status: res.status(),
+            golden_match: None,
+            captured: self.capture_headers.iter().map(header).collect(),
+            server_time: self
+                .server_time_header
+                .as_ref()
+                .and_then(header)
+                .and_then(|v| parse_header_duration(&v)),
+            body_size: None,
+            truncated: false,
+            content_encoding: header(&CONTENT_ENCODING),
+            ttfb,
+            ttlb: None,
+            version: res.version(),
+            conditional,
+            redirects,
+        };
+
+        if let (Some(etag), Some(value)) = (&self.etag, res.headers().get(ETAG)) {
+            *etag.lock().expect("etag lock poisoned") = Some(value.clone());
+        }
+
+        if !self.reads_body() {
+            return Ok(received);
+        }
+
+        // The body is only kept to be compared with the golden body.
+        let mut body = vec![];
+        let mut size = 0;
+        while let Some(chunk) = res.chunk().await? {
+            let chunk = match self.max_response_bytes {
+                Some(max) if size + chunk.len() as u64 > max => {
+                    received.truncated = true;
+                    chunk.slice(..(max - size) as usize)
+                }
+                _ => chunk,
+            };
+            size += chunk.len() as u64;
+            if self.golden.is_some() {
+                body.extend_from_slice(&chunk);
+            }
+            if received.truncated {
+                break;
+            }
         }
+        received.ttlb = Some(before.elapsed());
+        received.body_size = Some(size);
 
-        req
+        if let Some(golden) = &self.golden {
+            // A truncated body is only a prefix of the response, so it
+            // can not be told to match.
+            let golden_match = !received.truncated && body == *golden;
+            if !golden_match {
+                let _ = self.golden_mismatch.set(body);
+            }
+            received.golden_match = Some(golden_match);
+        }
+
+        Ok(received)
+    }
+
+    /// Returns the URL of the request with the dispatch index `i`.
+    fn url(&self, i: usize) -> &Url {
+        let j = self.sticky.as_ref().map_or(i, |s| s.route(i));
+        &self.urls[j % self.urls.len()]
+    }
+
+    /// Returns the body of the request with the given method and dispatch
+    /// index `i`, preferring the body of the method, if any.
+    fn body(&self, i: usize, method: &Method) -> Option<&Body> {
+        self.method_bodies
+            .get(method)
+            .or_else(|| (!self.bodies.is_empty()).then(|| &self.bodies[i % self.bodies.len()]))
+    }
+
+    /// Fails if reused bodies are rejected and the body of the request
+    /// with the dispatch index `i` is expected to vary, but equals
+    /// the body of a request with another index. A retried request may
+    /// send the same body again.
+    fn verify_fresh_body(&self, i: usize, req: &Request) -> Result<(), String> {
+        let Some(hashes) = &self.body_hashes else {
+            return Ok(());
+        };
+        if !self.body(i, req.method()).is_some_and(Body::varies) {
+            return Ok(());
+        }
+        let Some(bytes) = req.body().and_then(reqwest::Body::as_bytes) else {
+            return Ok(());
+        };
+
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        match hashes
+            .lock()
+            .expect("body hashes lock poisoned")
+            .entry(hasher.finish())
+        {
+            Entry::Occupied(e) if *e.get() != i => Err(format!(
+                "the body of request {i} equals the one of request {}, although it is rendered \
+                per request",
+                e.get()
+            )),
+            Entry::Occupied(_) => Ok(()),
+            Entry::Vacant(e) => {
+                e.insert(i);
+                Ok(())
+            }
+        }
+    }
+
+    /// Creates the request with the dispatch index `i` and returns it
+    /// along with the size of its body, if any.
+    fn create_request(&self, i: usize) -> (Request, Option<u64>) {
+        let mut url = self.url(i).clone();
+        if let Some(param) = &self.cache_bust {
+            let v: u64 = rand::thread_rng().gen();
+            url.query_pairs_mut().append_pair(param, &format!("{v:x}"));
+        }
+
+        let method = self.methods[i % self.methods.len()].clone();
+
+        let mut req = Request::new(method, url);
+        *req.timeout_mut() = Some(
+            self.timeout
+                .as_ref()
+                .map_or(DEFAULT_TIMEOUT, DurationRange::get_random),
+        );
+        if let Some(host) = &self.host {
+            req.headers_mut().insert(HOST, host.clone());
+        }
+        if let (Some(sticky), Some(name)) = (&self.sticky, &self.sticky_header) {
+            let key = Sticky::key(sticky.user(i));
+            let value = HeaderValue::from_str(&key).expect("user keys are valid header values");
+            req.headers_mut().insert(name, value);
+        }
+        if let Some(etag) = &self.etag {
+            if let Some(value) = etag.lock().expect("etag lock poisoned").clone() {
+                req.headers_mut().insert(IF_NONE_MATCH, value);
+            }
+        }
+        let body = self.body(i, req.method()).map(|b| b.render(i));
+
+        // The signature depends on the time and the body, so that each
+        // request is signed separately.
+        #[cfg(feature = "aws-sigv4")]
+        if let Some(signer) = &self.aws_sigv4 {
+            signer.sign(&mut req, body.as_deref().unwrap_or_default(), Utc::now());
+        }
+
+        let size = body.as_ref().map(|b| b.len() as u64);
+        if let Some(body) = body {
+            // A body sent via a channel has no known length and is
+            // therefore sent with chunked transfer encoding.
+            *req.body_mut() = Some(if self.chunked {
+                let (mut tx, body_rx) = hyper::Body::channel();
+                // The channel buffers a single chunk, so that sending it
+                // never fails.
+                let _ = tx.try_send_data(body.into());
+                body_rx.into()
+            } else {
+                body.into()
+            });
+        }
+
+        (req, size)
+    }
+}
+
+/// Parses the URL, prepending `default_scheme` if the URL has no scheme,
+/// e.g. 'example.com/api'. Without a default scheme, such URLs are
+/// rejected with an error suggesting the fix, because they would either
+/// fail to parse or, if they contain a port, be parsed with the host as
+/// scheme.
+fn parse_url(url: &str, default_scheme: Option<&str>) -> Result<Url> {
+    if url.contains("://") {
+        return url
+            .parse()
+            .map_err(|err| anyhow::anyhow!("invalid URL '{url}': {err}"));
+    }
+
+    match default_scheme {
+        Some(scheme) => format!("{scheme}://{url}")
+            .parse()
+            .map_err(|err| anyhow::anyhow!("invalid URL '{url}': {err}")),
+        None => anyhow::bail!(
+            "invalid URL '{url}': the scheme is missing; use 'http://{url}' or \
+            'https://{url}', or set a default scheme via `default-scheme`"
+        ),
+    }
+}
+
+/// Returns the resolution of the monotonic clock the latencies are
+/// measured with, i.e. the smallest observed step between two readings.
+/// On fine clocks, this is bounded by the cost of a reading instead.
+pub fn clock_resolution() -> Duration {
+    let next_tick = |t: Instant| loop {
+        let now = Instant::now();
+        if now > t {
+            return now;
+        }
+    };
+
+    // The first tick ends a partial step.
+    let mut last = next_tick(Instant::now());
+    (0..CLOCK_TICKS)
+        .map(|_| {
+            let now = next_tick(last);
+            let step = now - last;
+            last = now;
+            step
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+/// Limits the timeout of the request, so that it is aborted at `stop`.
+fn cap_timeout(req: &mut Request, stop: Option<Instant>) {
+    if let Some(stop) = stop {
+        let remaining = stop.saturating_duration_since(Instant::now());
+        let timeout = req.timeout_mut();
+        *timeout = Some(timeout.map_or(remaining, |t| t.min(remaining)));
+    }
+}
+
+/// Follows redirects like the default policy of reqwest and records the
+/// response of each redirect in the redirects of the current task.
+fn trace_redirect(attempt: redirect::Attempt) -> redirect::Action {
+    if let Some(url) = attempt.previous().last() {
+        let hop = Hop {
+            status: attempt.status(),
+            url: url.clone(),
+        };
+        // Redirects are only followed within `Client::execute`, which
+        // sets the redirects of the task.
+        let _ = REDIRECTS.try_with(|r| r.borrow_mut().push(hop));
+    }
+    if attempt.previous().len() > MAX_REDIRECTS {
+        attempt.error("too many redirects")
+    } else {
+        attempt.follow()
+    }
+}
+
+/// Resolves hosts via the system resolver like the default resolver of
+/// reqwest while counting the lookups, each of which is performed when
+/// a new connection is established.
+#[derive(Default)]
+struct CountingResolver {
+    lookups: AtomicUsize,
+}
+
+impl Resolve for CountingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs = tokio::task::spawn_blocking(move || (host.as_str(), 0).to_socket_addrs())
+                .await?
+                .map_err(DnsError)?;
+            Ok(Box::new(addrs) as Addrs)
+        })
     }
 }
 
+/// The error of a failed host lookup, which tells DNS failures apart
+/// from the other errors of establishing a connection.
+#[derive(Debug)]
+struct DnsError(io::Error);
+
+impl fmt::Display for DnsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Error for DnsError {}
+
+/// Parses headers in the format 'key: value', one per line; blank lines
+/// and lines starting with '#' are skipped.
+pub fn parse_headers(content: &str) -> Result<Vec<String>> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(n, l)| (n + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .map(|(n, l)| {
+            into_header_map(&[l.to_string()])
+                .map(|_| l.to_string())
+                .map_err(|err| anyhow::anyhow!("line {n}: {err}"))
+        })
+        .collect()
+}
+
+/// Sets the charset parameter of the Content-Type header which takes
+/// precedence, replacing any charset it already declares; a 'text/plain'
+/// header is added if none has been given.
+pub fn set_charset(headers: &mut Vec<String>, charset: &str) {
+    let content_type = headers.iter_mut().rev().find(|h| {
+        h.split_once(':')
+            .is_some_and(|(k, _)| k.trim().eq_ignore_ascii_case("content-type"))
+    });
+
+    match content_type {
+        Some(header) => {
+            let (key, value) = header.split_once(':').unwrap_or_default();
+            let params: Vec<_> = value
+                .split(';')
+                .map(str::trim)
+                .filter(|p| !p.to_ascii_lowercase().starts_with("charset="))
+                .collect();
+            *header = format!("{key}: {}; charset={charset}", params.join("; "));
+        }
+        None => headers.push(format!("Content-Type: text/plain; charset={charset}")),
+    }
+}
+
+fn parse_header_name(name: &str) -> Result<HeaderName> {
+    HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|_| anyhow::anyhow!("invalid header name: '{name}'"))
+}
+
 fn into_header_map(headers: &[String]) -> Result<HeaderMap> {
     let mut header_map = HeaderMap::new();
 
@@ -95,3 +1283,621 @@ fn into_header_map(headers: &[String]) -> Result<HeaderMap> {
 
     Ok(header_map)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{SocketAddr, TcpListener},
+        thread::{self, JoinHandle},
+    };
+
+    /// An empty response which closes the connection.
+    const OK: &[u8] = b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+
+    /// Serves the given amount of requests on a local port and answers
+    /// each with the response `respond` returns for the lines of its head.
+    /// A connection is kept open for the next request unless the response
+    /// closes it. The server returns the heads of the requests.
+    fn serve(
+        requests: usize,
+        respond: impl Fn(&[String]) -> Vec<u8> + Send + 'static,
+    ) -> (SocketAddr, JoinHandle<Vec<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let mut heads = vec![];
+            while heads.len() < requests {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                while heads.len() < requests {
+                    let head: Vec<_> = (&mut reader)
+                        .lines()
+                        .map(Result::unwrap)
+                        .take_while(|l| !l.is_empty())
+                        .collect();
+                    if head.is_empty() {
+                        break;
+                    }
+                    let res = respond(&head);
+                    // The client may stop reading early, e.g. a truncated
+                    // body, which is not an error of the server.
+                    let _ = (&stream).write_all(&res);
+                    heads.push(head);
+                    if String::from_utf8_lossy(&res).contains("connection: close") {
+                        break;
+                    }
+                }
+            }
+            heads
+        });
+        (addr, server)
+    }
+
+    fn block_on<T>(f: impl std::future::Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(f)
+    }
+
+    #[test]
+    fn cache_bust() {
+        let client = Client::new(ClientConfig {
+            urls: vec!["http://localhost/?a=1".into()],
+            methods: vec!["GET".into()],
+            cache_bust: Some("_".into()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let (a, _) = client.create_request(0);
+        let (b, _) = client.create_request(1);
+
+        assert!(a.url().as_str().starts_with("http://localhost/?a=1&_="));
+        assert_ne!(a.url(), b.url());
+    }
+
+    #[test]
+    fn clock() {
+        let resolution = clock_resolution();
+        assert!(!resolution.is_zero());
+        assert!(resolution < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn url_without_scheme() {
+        let client = |url: &str, default_scheme: Option<&str>| {
+            Client::new(ClientConfig {
+                urls: vec![url.into()],
+                default_scheme: default_scheme.map(String::from),
+                methods: vec!["GET".into()],
+                ..Default::default()
+            })
+        };
+
+        for url in ["example.com/api", "localhost:8080/api"] {
+            let err = client(url, None).err().unwrap().to_string();
+            assert!(err.contains("the scheme is missing"), "{err}");
+            assert!(err.contains(&format!("'https://{url}'")), "{err}");
+        }
+
+        let c = client("localhost:8080/api", Some("https")).unwrap();
+        assert_eq!("https://localhost:8080/api", c.url(0).as_str());
+        let c = client("http://example.com/", Some("https")).unwrap();
+        assert_eq!("http://example.com/", c.url(0).as_str());
+
+        assert!(client("http://exa mple.com/", None).is_err());
+    }
+
+    #[test]
+    fn tls_versions() {
+        assert_eq!(TlsVersion::Tls12, "1.2".parse().unwrap());
+        assert_eq!(TlsVersion::Tls13, "1.3".parse().unwrap());
+        assert!("1.1".parse::<TlsVersion>().is_err());
+
+        let client = |min, max| {
+            Client::new(ClientConfig {
+                urls: vec!["https://localhost/".into()],
+                methods: vec!["GET".into()],
+                tls_min_version: Some(min),
+                tls_max_version: Some(max),
+                ..Default::default()
+            })
+        };
+        assert!(client(TlsVersion::Tls12, TlsVersion::Tls13).is_ok());
+        assert!(client(TlsVersion::Tls13, TlsVersion::Tls13).is_ok());
+        assert!(client(TlsVersion::Tls13, TlsVersion::Tls12).is_err());
+    }
+
+    #[test]
+    fn url_cycle() {
+        let client = Client::new(ClientConfig {
+            urls: vec!["http://a.localhost/".into(), "http://b.localhost/".into()],
+            methods: vec!["GET".into()],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let hosts: Vec<_> = (0..3)
+            .map(|i| {
+                client
+                    .create_request(i)
+                    .0
+                    .url()
+                    .host_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(vec!["a.localhost", "b.localhost", "a.localhost"], hosts);
+    }
+
+    #[test]
+    fn method_cycle() {
+        let client = Client::new(ClientConfig {
+            urls: vec!["http://localhost/".into()],
+            methods: vec!["POST".into(), "GET".into(), "DELETE".into()],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let methods: Vec<_> = (0..5)
+            .map(|i| client.create_request(i).0.method().to_string())
+            .collect();
+        assert_eq!(vec!["POST", "GET", "DELETE", "POST", "GET"], methods);
+
+        let res = Client::new(ClientConfig {
+            urls: vec!["http://localhost/".into()],
+            methods: vec!["GE T".into()],
+            ..Default::default()
+        });
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn body_cycle() {
+        let client = Client::new(ClientConfig {
+            urls: vec!["http://localhost/".into()],
+            methods: vec!["POST".into()],
+            bodies: vec![Body::Raw(b"a".to_vec()), Body::Raw(b"b".to_vec())],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let bodies: Vec<_> = (0..3)
+            .map(|i| {
+                let (req, size) = client.create_request(i);
+                assert_eq!(Some(1), size);
+                req.body().and_then(|b| b.as_bytes()).unwrap().to_vec()
+            })
+            .collect();
+        assert_eq!(vec![b"a".to_vec(), b"b".to_vec(), b"a".to_vec()], bodies);
+    }
+
+    #[test]
+    fn charset() {
+        let mut headers = vec!["Accept: */*".to_string()];
+        set_charset(&mut headers, "Shift_JIS");
+        assert_eq!("Content-Type: text/plain; charset=Shift_JIS", headers[1]);
+
+        let mut headers = vec![
+            "content-type: text/html".to_string(),
+            "content-type: application/json; charset=utf-8; a=b".to_string(),
+        ];
+        set_charset(&mut headers, "EUC-JP");
+        assert_eq!("content-type: text/html", headers[0]);
+        assert_eq!(
+            "content-type: application/json; a=b; charset=EUC-JP",
+            headers[1]
+        );
+    }
+
+    #[test]
+    fn method_bodies() {
+        let client = Client::new(ClientConfig {
+            urls: vec!["http://localhost/".into()],
+            methods: vec!["GET".into(), "POST".into(), "PUT".into()],
+            method_bodies: vec![
+                ("POST".into(), Body::Raw(b"created".to_vec())),
+                ("PUT".into(), Body::Raw(b"updated".to_vec())),
+            ],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let bodies: Vec<_> = (0..3)
+            .map(|i| {
+                let (req, size) = client.create_request(i);
+                let body = req.body().and_then(|b| b.as_bytes()).map(<[u8]>::to_vec);
+                assert_eq!(size, body.as_ref().map(|b| b.len() as u64));
+                body
+            })
+            .collect();
+        assert_eq!(
+            vec![None, Some(b"created".to_vec()), Some(b"updated".to_vec())],
+            bodies
+        );
+        assert_eq!(Some("content-length"), client.body_encoding());
+
+        let res = Client::new(ClientConfig {
+            urls: vec!["http://localhost/".into()],
+            methods: vec!["GET".into()],
+            method_bodies: vec![("PO ST".into(), Body::Raw(vec![]))],
+            ..Default::default()
+        });
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn no_body_reuse() {
+        let client = |body: &[u8]| {
+            Client::new(ClientConfig {
+                urls: vec!["http://localhost/".into()],
+                methods: vec!["POST".into()],
+                bodies: vec![Body::new(body.to_vec(), true)],
+                no_body_reuse: true,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        let fresh = client(b"{\"id\":{{n}}}");
+        let bodies: Vec<_> = (0..10)
+            .map(|i| {
+                let (req, _) = fresh.create_request(i);
+                assert_eq!(Ok(()), fresh.verify_fresh_body(i, &req));
+                req.body().and_then(|b| b.as_bytes()).unwrap().to_vec()
+            })
+            .collect();
+        let distinct: std::collections::HashSet<_> = bodies.iter().collect();
+        assert_eq!(bodies.len(), distinct.len());
+
+        // A retried request sends the same body again.
+        let (req, _) = fresh.create_request(3);
+        assert_eq!(Ok(()), fresh.verify_fresh_body(3, &req));
+
+        // The body of request 0 is sent again by request 1.
+        let (req, _) = fresh.create_request(0);
+        assert!(fresh.verify_fresh_body(1, &req).is_err());
+
+        // A template without placeholders renders the same body each
+        // time, which is not checked.
+        let constant = client(b"static");
+        for i in 0..2 {
+            let (req, _) = constant.create_request(i);
+            assert_eq!(Ok(()), constant.verify_fresh_body(i, &req));
+        }
+    }
+
+    #[test]
+    fn headers_file() {
+        let headers = parse_headers("# auth\nX-A: 1\n\n  X-B: 2  \n").unwrap();
+        assert_eq!(vec!["X-A: 1", "X-B: 2"], headers);
+
+        // Later headers override earlier ones with the same key.
+        let map = into_header_map(&[headers, vec!["x-a: 3".into()]].concat()).unwrap();
+        assert_eq!("3", map["x-a"]);
+
+        let err = parse_headers("X-A: 1\nbroken\n").unwrap_err();
+        assert_eq!("line 2: invalid header format", err.to_string());
+    }
+
+    #[test]
+    fn host_header() {
+        let (addr, server) = serve(1, |_| OK.to_vec());
+
+        let client = Client::new(ClientConfig {
+            urls: vec![format!("http://{addr}/")],
+            methods: vec!["GET".into()],
+            headers: vec!["Host: ignored.example.com".into()],
+            host: Some("example.com".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        block_on(client.send(0)).unwrap();
+
+        let heads = server.join().unwrap();
+        let host_headers: Vec<_> = heads[0]
+            .iter()
+            .filter(|l| l.to_lowercase().starts_with("host:"))
+            .collect();
+        assert_eq!(vec!["host: example.com"], host_headers);
+    }
+
+    #[test]
+    fn inject_errors() {
+        let client = |pct, seed| {
+            Client::new(ClientConfig {
+                urls: vec!["http://localhost/".into()],
+                methods: vec!["GET".into()],
+                inject_errors: Some(pct),
+                seed,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let injected = |c: &Client| (0..1000).filter(|&i| c.inject_error(i)).collect::<Vec<_>>();
+
+        assert!(injected(&client(0f64, 1)).is_empty());
+        assert_eq!(1000, injected(&client(100f64, 1)).len());
+
+        let a = injected(&client(20f64, 1));
+        assert!((150..250).contains(&a.len()));
+        assert_eq!(a, injected(&client(20f64, 1)));
+        assert_ne!(a, injected(&client(20f64, 2)));
+    }
+
+    #[test]
+    fn chunked_body() {
+        let (addr, server) = serve(1, |_| OK.to_vec());
+
+        let client = Client::new(ClientConfig {
+            urls: vec![format!("http://{addr}/")],
+            methods: vec!["POST".into()],
+            bodies: vec![Body::new(b"hello".to_vec(), false)],
+            chunked: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(Some("chunked"), client.body_encoding());
+        block_on(client.send(0)).unwrap();
+
+        let headers: Vec<_> = server.join().unwrap()[0]
+            .iter()
+            .map(|l| l.to_lowercase())
+            .collect();
+        assert!(headers.iter().any(|h| h == "transfer-encoding: chunked"));
+        assert!(!headers.iter().any(|h| h.starts_with("content-length")));
+    }
+
+    #[test]
+    fn conditional() {
+        fn if_none_match(head: &[String]) -> Option<&str> {
+            head.iter().find_map(|l| l.strip_prefix("if-none-match: "))
+        }
+
+        let (addr, server) = serve(2, |head| {
+            let res: &[u8] = match if_none_match(head) {
+                Some("\"v1\"") => b"HTTP/1.1 304 Not Modified\r\nconnection: close\r\n\r\n",
+                _ => b"HTTP/1.1 200 OK\r\netag: \"v1\"\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            };
+            res.to_vec()
+        });
+
+        let client = Client::new(ClientConfig {
+            urls: vec![format!("http://{addr}/")],
+            methods: vec!["GET".into()],
+            conditional: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let first = block_on(client.send(0)).unwrap();
+        let second = block_on(client.send(1)).unwrap();
+
+        assert_eq!((StatusCode::OK, false), (first.status, first.conditional));
+        assert_eq!(
+            (StatusCode::NOT_MODIFIED, true),
+            (second.status, second.conditional)
+        );
+        let heads = server.join().unwrap();
+        let seen: Vec<_> = heads.iter().map(|h| if_none_match(h)).collect();
+        assert_eq!(vec![None, Some("\"v1\"")], seen);
+    }
+
+    #[test]
+    fn trace_redirects() {
+        let (addr, server) = serve(3, |head| {
+            let res: &[u8] = match head[0].split(' ').nth(1) {
+                Some("/old") => b"HTTP/1.1 301 Moved Permanently\r\nlocation: /new\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                _ => OK,
+            };
+            res.to_vec()
+        });
+
+        let client = Client::new(ClientConfig {
+            urls: vec![format!("http://{addr}/old"), format!("http://{addr}/new")],
+            methods: vec!["GET".into()],
+            trace_redirects: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let redirected = block_on(client.send(0)).unwrap();
+        let direct = block_on(client.send(1)).unwrap();
+        server.join().unwrap();
+
+        let hops: Vec<_> = redirected
+            .redirects
+            .iter()
+            .map(|h| (h.status, h.url.path()))
+            .collect();
+        assert_eq!(
+            vec![
+                (StatusCode::MOVED_PERMANENTLY, "/old"),
+                (StatusCode::OK, "/new")
+            ],
+            hops
+        );
+        assert!(direct.redirects.is_empty());
+    }
+
+    #[test]
+    fn no_decompress() {
+        // "hello hello hello hello" compressed with gzip.
+        const GZIP: [u8; 28] = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9,
+            0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01, 0xe3, 0x51, 0x3d, 0x8d, 0x17, 0x00, 0x00, 0x00,
+        ];
+
+        let (addr, _) = serve(2, |_| {
+            let head: &[u8] = b"HTTP/1.1 200 OK\r\ncontent-encoding: gzip\r\n\
+                content-length: 28\r\nconnection: close\r\n\r\n";
+            [head, &GZIP].concat()
+        });
+
+        let send = |no_decompress| {
+            let client = Client::new(ClientConfig {
+                urls: vec![format!("http://{addr}/")],
+                methods: vec!["GET".into()],
+                drain_body: true,
+                no_decompress,
+                ..Default::default()
+            })
+            .unwrap();
+            block_on(client.send(0)).unwrap()
+        };
+
+        let res = send(false);
+        assert_eq!((Some(23), None), (res.body_size, res.content_encoding));
+
+        let res = send(true);
+        assert_eq!(Some(28), res.body_size);
+        assert_eq!(Some("gzip"), res.content_encoding.as_deref());
+    }
+
+    #[test]
+    fn max_response_bytes() {
+        let (addr, _) = serve(2, |_| {
+            b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\nconnection: close\r\n\r\n0123456789".to_vec()
+        });
+
+        let send = |max| {
+            let client = Client::new(ClientConfig {
+                urls: vec![format!("http://{addr}/")],
+                methods: vec!["GET".into()],
+                golden: Some(b"0123".to_vec()),
+                max_response_bytes: Some(max),
+                ..Default::default()
+            })
+            .unwrap();
+            block_on(client.send(0)).unwrap()
+        };
+
+        let res = send(4);
+        assert!(res.truncated);
+        assert_eq!((Some(4), Some(false)), (res.body_size, res.golden_match));
+        assert_eq!("200 OK (truncated)", res.status_label());
+
+        let res = send(10);
+        assert!(!res.truncated);
+        assert_eq!((Some(10), Some(false)), (res.body_size, res.golden_match));
+    }
+
+    #[test]
+    fn accept_encoding() {
+        let (addr, server) = serve(4, |_| OK.to_vec());
+
+        let send = |accept_encoding: Option<&str>, no_decompress| {
+            let client = Client::new(ClientConfig {
+                urls: vec![format!("http://{addr}/")],
+                methods: vec!["GET".into()],
+                no_decompress,
+                accept_encoding: accept_encoding.map(String::from),
+                ..Default::default()
+            })
+            .unwrap();
+            block_on(client.send(0)).unwrap();
+        };
+        send(None, false);
+        send(None, true);
+        send(Some("zstd"), false);
+        send(Some(NO_ENCODING), false);
+
+        let values: Vec<_> = server
+            .join()
+            .unwrap()
+            .iter()
+            .map(|head| {
+                head.iter()
+                    .find_map(|l| Some(l.strip_prefix("accept-encoding: ")?.to_string()))
+            })
+            .collect();
+        assert_eq!(Some("gzip, br, deflate"), values[0].as_deref());
+        assert_eq!(Some("gzip, br, deflate"), values[1].as_deref());
+        assert_eq!(Some("zstd"), values[2].as_deref());
+        assert_eq!(None, values[3]);
+    }
+
+    #[test]
+    fn connection_count() {
+        let (addr, server) = serve(3, |_| {
+            b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok".to_vec()
+        });
+
+        let client = Client::new(ClientConfig {
+            urls: vec![format!("http://localhost:{}/", addr.port())],
+            methods: vec!["GET".into()],
+            drain_body: true,
+            ..Default::default()
+        })
+        .unwrap();
+        // The connections are bound to the runtime they were established
+        // on, so that all requests are sent on the same runtime.
+        block_on(async {
+            for i in 0..3 {
+                client.send(i).await.unwrap();
+            }
+        });
+        server.join().unwrap();
+
+        assert_eq!(Some(1), client.connections());
+    }
+
+    #[test]
+    fn default_timeout() {
+        // The server accepts the connection, but never answers.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || listener.accept().unwrap());
+
+        let client = Client::new(ClientConfig {
+            urls: vec![format!("http://{addr}/")],
+            methods: vec!["GET".into()],
+            ..Default::default()
+        })
+        .unwrap();
+        // The paused clock skips ahead to the timeout once the runtime
+        // is idle, so that the test does not wait for it; a request
+        // without a timeout would never complete.
+        let failure = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .start_paused(true)
+            .build()
+            .unwrap()
+            .block_on(async { tokio::time::timeout(DEFAULT_TIMEOUT * 2, client.send(0)).await })
+            .expect("the request has no timeout")
+            .unwrap_err();
+        assert_eq!(FailureKind::Timeout, failure.kind);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn failure_kinds() {
+        let fail = |url: String| {
+            let client = Client::new(ClientConfig {
+                urls: vec![url],
+                methods: vec!["GET".into()],
+                ..Default::default()
+            })
+            .unwrap();
+            block_on(client.send(0)).unwrap_err()
+        };
+
+        // The port is closed once the listener has been dropped.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let refused = fail(format!("http://127.0.0.1:{port}/?a=1"));
+        assert_eq!(FailureKind::Connect, refused.kind);
+        assert!(refused.message.contains(&port.to_string()));
+        assert!(!refused.reason.contains(&port.to_string()));
+
+        let unresolved = fail("http://rush.invalid/".into());
+        assert_eq!(FailureKind::Dns, unresolved.kind);
+        assert!(!unresolved.reason.contains("rush.invalid"));
+    }
+}
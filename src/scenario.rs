@@ -0,0 +1,51 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::{
+    fs::File,
+    io::BufReader,
+    num::{NonZeroU32, NonZeroUsize},
+    path::Path,
+};
+
+/// A set of targets which are benchmarked concurrently, each with
+/// its own request settings.
+#[derive(Debug, Deserialize)]
+pub struct Scenario {
+    pub targets: Vec<Target>,
+}
+
+/// A single target of a [`Scenario`]. Unset values fall back to the
+/// values passed via the command line.
+#[derive(Debug, Default, Deserialize)]
+pub struct Target {
+    pub name: Option<String>,
+    pub url: String,
+    pub method: Option<String>,
+    /// Headers in the format 'key: value', which are sent in addition
+    /// to the headers passed via the command line.
+    #[serde(default)]
+    pub headers: Vec<String>,
+    pub body: Option<String>,
+    pub count: Option<NonZeroU32>,
+    pub parallel: Option<NonZeroUsize>,
+}
+
+impl Scenario {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let f = File::open(path)?;
+        let scenario: Self = serde_json::from_reader(BufReader::new(f))?;
+
+        if scenario.targets.is_empty() {
+            anyhow::bail!("the scenario does not contain any targets");
+        }
+
+        Ok(scenario)
+    }
+}
+
+impl Target {
+    /// Returns the name of the target or its URL, if no name is set.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.url)
+    }
+}
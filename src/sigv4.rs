@@ -0,0 +1,388 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::{
+    header::{HeaderName, HeaderValue, AUTHORIZATION, HOST},
+    Request, Url,
+};
+use sha2::{Digest, Sha256};
+use std::{env, fmt, fmt::Write};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// The block size of SHA-256, which HMAC pads the key to.
+const BLOCK_SIZE: usize = 64;
+
+/// The credentials requests are signed with.
+#[derive(Clone)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// The token of temporary credentials, which is sent in the
+    /// X-Amz-Security-Token header.
+    pub session_token: Option<String>,
+}
+
+impl Credentials {
+    /// Reads the credentials from the `AWS_ACCESS_KEY_ID`,
+    /// `AWS_SECRET_ACCESS_KEY` and, optionally, `AWS_SESSION_TOKEN`
+    /// environment variables.
+    pub fn from_env() -> Result<Self> {
+        let var = |name| {
+            env::var(name)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| anyhow::anyhow!("the environment variable {name} is not set"))
+        };
+        Ok(Self {
+            access_key_id: var("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: var("AWS_SECRET_ACCESS_KEY")?,
+            session_token: var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Signs requests with the AWS Signature Version 4.
+///
+/// The signature covers the method, the path, the query, the Host and
+/// X-Amz-Date headers and the SHA-256 hash of the body, so that each
+/// request is signed separately. For S3, the hash of the body is also
+/// sent in the X-Amz-Content-Sha256 header, which it requires.
+#[derive(Debug)]
+pub struct Signer {
+    region: String,
+    service: String,
+    credentials: Credentials,
+    session_token: Option<HeaderValue>,
+}
+
+impl Signer {
+    /// Creates a signer from a 'region:service' spec, e.g.
+    /// 'eu-central-1:execute-api'.
+    pub fn new(spec: &str, credentials: Credentials) -> Result<Self> {
+        let (region, service) = spec
+            .split_once(':')
+            .filter(|(r, s)| is_scope_part(r) && is_scope_part(s))
+            .ok_or_else(|| anyhow::anyhow!("expected 'region:service', got '{spec}'"))?;
+        if !is_scope_part(&credentials.access_key_id) {
+            anyhow::bail!("invalid access key id");
+        }
+        let session_token = credentials
+            .session_token
+            .as_deref()
+            .map(|t| {
+                t.parse()
+                    .map_err(|_| anyhow::anyhow!("invalid session token"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            region: region.into(),
+            service: service.into(),
+            credentials,
+            session_token,
+        })
+    }
+
+    /// Adds the signature of the request with the given body at `now` to
+    /// its headers. The Host header is derived from the URL unless it
+    /// has been set explicitly.
+    pub fn sign(&self, req: &mut Request, body: &[u8], now: DateTime<Utc>) {
+        let date = now.format("%Y%m%d").to_string();
+        let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex(&Sha256::digest(body));
+
+        let host = match req.headers().get(HOST) {
+            Some(host) => String::from_utf8_lossy(host.as_bytes()).into_owned(),
+            None => host(req.url()),
+        };
+
+        let mut headers = vec![("host", host), ("x-amz-date", timestamp.clone())];
+        if self.service == "s3" {
+            headers.push(("x-amz-content-sha256", payload_hash.clone()));
+        }
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        // The canonical headers are sorted by their lowercase names.
+        headers.sort_by_key(|(name, _)| *name);
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let mut canonical = String::new();
+        let _ = write!(
+            canonical,
+            "{}\n{}\n{}\n",
+            req.method(),
+            canonical_path(req.url(), self.service != "s3"),
+            canonical_query(req.url()),
+        );
+        for (name, value) in &headers {
+            let _ = writeln!(canonical, "{name}:{}", value.trim());
+        }
+        let _ = write!(canonical, "\n{signed_headers}\n{payload_hash}");
+
+        let scope = format!("{date}/{}/{}/aws4_request", self.region, self.service);
+        let string_to_sign = format!(
+            "{ALGORITHM}\n{timestamp}\n{scope}\n{}",
+            hex(&Sha256::digest(canonical.as_bytes()))
+        );
+
+        let key = [
+            date.as_bytes(),
+            self.region.as_bytes(),
+            self.service.as_bytes(),
+            b"aws4_request",
+        ]
+        .iter()
+        .fold(
+            format!("AWS4{}", self.credentials.secret_access_key).into_bytes(),
+            |key, part| hmac_sha256(&key, part).to_vec(),
+        );
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        let authorization = format!(
+            "{ALGORITHM} Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.credentials.access_key_id
+        );
+
+        let h = req.headers_mut();
+        h.insert(
+            HeaderName::from_static("x-amz-date"),
+            timestamp
+                .parse()
+                .expect("the timestamp is a valid header value"),
+        );
+        if self.service == "s3" {
+            h.insert(
+                HeaderName::from_static("x-amz-content-sha256"),
+                payload_hash
+                    .parse()
+                    .expect("the hash is a valid header value"),
+            );
+        }
+        if let Some(token) = &self.session_token {
+            h.insert(
+                HeaderName::from_static("x-amz-security-token"),
+                token.clone(),
+            );
+        }
+        h.insert(
+            AUTHORIZATION,
+            authorization
+                .parse()
+                .expect("the scope has been validated to be a valid header value"),
+        );
+    }
+}
+
+fn is_scope_part(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_graphic() && b != b'/')
+}
+
+/// Returns the host of the URL as sent in the Host header, i.e. with the
+/// port only if it is not the default port of the scheme.
+fn host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.into(),
+    }
+}
+
+/// Returns the URI-encoded path; each segment is encoded twice for all
+/// services except S3.
+fn canonical_path(url: &Url, double_encode: bool) -> String {
+    let path = url.path();
+    if path.is_empty() {
+        return "/".into();
+    }
+    path.split('/')
+        .map(|segment| {
+            let encoded = uri_encode(percent_decode(segment), false);
+            if double_encode {
+                uri_encode(&encoded, false)
+            } else {
+                encoded
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns the URI-encoded query parameters sorted by name and value.
+fn canonical_query(url: &Url) -> String {
+    let mut pairs: Vec<_> = url
+        .query_pairs()
+        .map(|(k, v)| {
+            (
+                uri_encode(k.as_bytes(), true),
+                uri_encode(v.as_bytes(), true),
+            )
+        })
+        .collect();
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Percent-encodes all bytes except the unreserved characters; '/' is
+/// only encoded if `encode_slash` is true.
+fn uri_encode(s: impl AsRef<[u8]>, encode_slash: bool) -> String {
+    let mut out = String::new();
+    for &b in s.as_ref() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            b'/' if !encode_slash => out.push('/'),
+            b => {
+                let _ = write!(out, "%{b:02X}");
+            }
+        }
+    }
+    out
+}
+
+fn percent_decode(s: &str) -> Vec<u8> {
+    let b = s.as_bytes();
+    let mut out = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        let decoded = (b[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match decoded {
+            Some(v) => {
+                out.push(v);
+                i += 3;
+            }
+            None => {
+                out.push(b[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let pad = |v: u8| block.iter().map(|b| b ^ v).collect::<Vec<_>>();
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(data)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+    use reqwest::Method;
+
+    #[test]
+    fn sign() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?"))
+        );
+
+        // The 'get-vanilla' case of the AWS Signature Version 4 test suite
+        let signer = Signer::new(
+            "us-east-1:service",
+            Credentials {
+                access_key_id: "AKIDEXAMPLE".into(),
+                secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+                session_token: None,
+            },
+        )
+        .unwrap();
+        let mut req = Request::new(
+            Method::GET,
+            "https://example.amazonaws.com/".parse().unwrap(),
+        );
+        let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+        signer.sign(&mut req, b"", now);
+
+        assert_eq!("20150830T123600Z", req.headers()["x-amz-date"]);
+        assert_eq!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+            SignedHeaders=host;x-amz-date, \
+            Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31",
+            req.headers()[AUTHORIZATION]
+        );
+
+        // An S3 upload with temporary credentials, signed by botocore
+        let signer = Signer::new(
+            "us-east-1:s3",
+            Credentials {
+                session_token: Some("FQoGZXIvYXdzEXAMPLETOKEN".into()),
+                ..signer.credentials.clone()
+            },
+        )
+        .unwrap();
+        let mut req = Request::new(
+            Method::PUT,
+            "https://examplebucket.s3.amazonaws.com/photos/a%20b.txt"
+                .parse()
+                .unwrap(),
+        );
+        signer.sign(&mut req, b"hello", now);
+
+        assert_eq!(
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+            req.headers()["x-amz-content-sha256"]
+        );
+        assert_eq!(
+            "FQoGZXIvYXdzEXAMPLETOKEN",
+            req.headers()["x-amz-security-token"]
+        );
+        assert_eq!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/s3/aws4_request, \
+            SignedHeaders=host;x-amz-content-sha256;x-amz-date;x-amz-security-token, \
+            Signature=2d32e9ba88d9de03677a10ca9b531fc8ce81e7c6cffd817a334cbb37213c3808",
+            req.headers()[AUTHORIZATION]
+        );
+
+        let url = "http://h/a%20b/c?b=2&a=x%2Fy&a=1".parse().unwrap();
+        assert_eq!("/a%2520b/c", canonical_path(&url, true));
+        assert_eq!("/a%20b/c", canonical_path(&url, false));
+        assert_eq!("a=1&a=x%2Fy&b=2", canonical_query(&url));
+
+        assert!(Signer::new("us-east-1", signer.credentials.clone()).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use rand::Rng;
+use std::{fmt, str::FromStr};
+
+/// Parses an amount of bytes with an optional decimal (e.g. '10MB') or
+/// binary (e.g. '10MiB') unit; the unit is case insensitive.
+pub fn parse_size(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid size: '{s}'"))?;
+
+    let factor = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1u64,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => anyhow::bail!("invalid size unit: '{unit}'; expected e.g. 'KB', 'MiB' or 'GB'"),
+    };
+
+    Ok((number * factor as f64) as u64)
+}
+
+/// A range of sizes in bytes.
+///
+/// Parsed from either a single size (e.g. '1MiB') or an inclusive range
+/// of sizes (e.g. '1KiB..1MiB') in the format of [`parse_size`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeRange {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl SizeRange {
+    pub fn get_random(&self, rng: &mut impl Rng) -> u64 {
+        rng.gen_range(self.min..=self.max)
+    }
+}
+
+impl FromStr for SizeRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((min, max)) = s.split_once("..") else {
+            let size = parse_size(s)?;
+            return Ok(Self {
+                min: size,
+                max: size,
+            });
+        };
+
+        let (min, max) = (parse_size(min)?, parse_size(max)?);
+        if min > max {
+            anyhow::bail!("the minimum size exceeds the maximum size");
+        }
+        Ok(Self { min, max })
+    }
+}
+
+/// Formats an amount of bytes with a binary unit, e.g. '1.50MiB'.
+pub fn format_bytes(bytes: f64) -> BytesFormatter {
+    BytesFormatter(bytes)
+}
+
+pub struct BytesFormatter(f64);
+
+impl fmt::Display for BytesFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (unit, v) = match self.0 {
+            b if b < 1024f64 => ("B", b),
+            b if b < 1024f64 * 1024f64 => ("KiB", b / 1024f64),
+            b if b < 1024f64 * 1024f64 * 1024f64 => ("MiB", b / 1024f64 / 1024f64),
+            b => ("GiB", b / 1024f64 / 1024f64 / 1024f64),
+        };
+        v.fmt(f)?;
+        f.write_str(unit)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(512, parse_size("512").unwrap());
+        assert_eq!(10_000_000, parse_size("10MB").unwrap());
+        assert_eq!(1536, parse_size("1.5KiB").unwrap());
+        assert_eq!(2 << 30, parse_size("2 gib").unwrap());
+        assert!(parse_size("10 apples").is_err());
+        assert!(parse_size("MB").is_err());
+
+        let range: SizeRange = "1KiB..2KB".parse().unwrap();
+        assert_eq!((1024, 2000), (range.min, range.max));
+        assert_eq!(SizeRange { min: 5, max: 5 }, "5".parse().unwrap());
+        assert!("2KB..1KB".parse::<SizeRange>().is_err());
+
+        assert_eq!("1.50MiB", format!("{:.2}", format_bytes(1572864f64)));
+        assert_eq!("12B", format!("{}", format_bytes(12f64)));
+    }
+}
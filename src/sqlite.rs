@@ -0,0 +1,119 @@
+use crate::{output::Record, stats::Stats};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_at TEXT NOT NULL,
+    url TEXT NOT NULL,
+    command TEXT NOT NULL,
+    summary TEXT
+);
+CREATE TABLE IF NOT EXISTS requests (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    timestamp INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    code INTEGER,
+    took_ns INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS requests_run_id ON requests(run_id);
+";
+
+/// The metadata of a run stored in the `runs` table.
+pub struct RunInfo<'a> {
+    pub started_at: DateTime<Utc>,
+    pub url: &'a str,
+    /// The command line the run has been started with.
+    pub command: &'a str,
+}
+
+/// Appends the run and its records to the SQLite database at the given
+/// path, creating the database and its tables if they do not exist.
+/// Timestamps of the records are stored as Unix nanoseconds and the
+/// summary as JSON. Returns the id of the inserted run.
+pub fn write_sqlite(
+    path: impl AsRef<Path>,
+    run: &RunInfo,
+    records: &[Record],
+    stats: Option<&Stats>,
+) -> Result<i64> {
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch(SCHEMA)?;
+
+    let summary = stats.map(serde_json::to_string).transpose()?;
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO runs (started_at, url, command, summary) VALUES (?1, ?2, ?3, ?4)",
+        params![run.started_at.to_rfc3339(), run.url, run.command, summary],
+    )?;
+    let run_id = tx.last_insert_rowid();
+
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO requests (run_id, timestamp, status, code, took_ns) \
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for r in records {
+            stmt.execute(params![
+                run_id,
+                r.timestamp.timestamp_nanos_opt().unwrap_or_default(),
+                r.status,
+                r.code,
+                r.took.as_nanos() as i64,
+            ])?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(run_id)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util;
+    use std::time::Duration;
+
+    #[test]
+    fn append_runs() {
+        let records = vec![
+            Record {
+                index: 0,
+                timestamp: Utc::now(),
+                code: Some(200),
+                status: "200 OK".into(),
+                took: Duration::from_millis(5),
+            },
+            Record {
+                index: 0,
+                timestamp: Utc::now(),
+                code: None,
+                status: "timeout".into(),
+                took: Duration::from_millis(7),
+            },
+        ];
+        let run = RunInfo {
+            started_at: Utc::now(),
+            url: "http://localhost/",
+            command: "rush http://localhost/",
+        };
+
+        let path = test_util::temp_dir("sqlite").join("append.db");
+        assert_eq!(1, write_sqlite(&path, &run, &records, None).unwrap());
+        assert_eq!(2, write_sqlite(&path, &run, &records, None).unwrap());
+
+        let conn = Connection::open(&path).unwrap();
+        let (n, timeouts): (i64, i64) = conn
+            .query_row(
+                "SELECT COUNT(*), SUM(code IS NULL) FROM requests WHERE run_id = 2",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((2, 1), (n, timeouts));
+    }
+}
@@ -0,0 +1,1525 @@
+use crate::{
+    duration::{format_duration, DurationUnit},
+    rate::RateReport,
+    request::{Failure, Response},
+    size::format_bytes,
+    tls::Handshakes,
+    urls::StickyTarget,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, collections::BTreeMap, time::Duration};
+
+/// Aggregated statistics over the results of a benchmark run.
+///
+/// All durations are serialized as nanoseconds.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Stats {
+    /// The amount of completed requests the latency metrics are
+    /// computed from.
+    pub count: usize,
+    /// The amount of requests which did not succeed, including the
+    /// requests which could not be completed.
+    #[serde(default)]
+    pub failed: usize,
+    /// The amount of requests which succeeded but have been marked as
+    /// failed by the error injection; included in `failed`.
+    #[serde(default)]
+    pub injected: usize,
+    /// The amount of requests answered with a 4xx or 5xx status, unlike
+    /// the requests which failed without a response; included in
+    /// `failed`.
+    #[serde(default)]
+    pub http_errors: usize,
+    /// The amount of responses whose bodies have been truncated after
+    /// the maximum amount of bytes.
+    #[serde(default)]
+    pub truncated: usize,
+    #[serde(with = "crate::duration::nanos")]
+    pub min: Duration,
+    #[serde(with = "status_code")]
+    pub min_status: StatusCode,
+    #[serde(with = "crate::duration::nanos")]
+    pub max: Duration,
+    #[serde(with = "status_code")]
+    pub max_status: StatusCode,
+    #[serde(with = "crate::duration::nanos")]
+    pub first: Duration,
+    #[serde(with = "status_code")]
+    pub first_status: StatusCode,
+    #[serde(with = "crate::duration::nanos")]
+    pub avg: Duration,
+    #[serde(with = "crate::duration::nanos")]
+    pub median: Duration,
+    #[serde(with = "crate::duration::nanos")]
+    pub stddev: Duration,
+    #[serde(with = "crate::duration::nanos")]
+    pub p90: Duration,
+    #[serde(with = "crate::duration::nanos")]
+    pub p95: Duration,
+    #[serde(with = "crate::duration::nanos")]
+    pub p99: Duration,
+    #[serde(with = "crate::duration::nanos")]
+    pub total: Duration,
+    pub statuses: BTreeMap<u16, u64>,
+    #[serde(default)]
+    pub methods: BTreeMap<String, u64>,
+    /// The latencies of the completed requests by their method, if more
+    /// than one method has been used.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub method_latencies: BTreeMap<String, Latencies>,
+    /// The amount of requests which could not be completed by the kind
+    /// of failure.
+    #[serde(default)]
+    pub errors: BTreeMap<String, u64>,
+    /// The most frequent distinct reasons of the failures.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error_reasons: Vec<ErrorReason>,
+    /// The most frequent chains of redirects, if redirects are traced.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirect_chains: Vec<RedirectChain>,
+    /// The amount of responses by their negotiated HTTP version.
+    #[serde(default)]
+    pub protocols: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<Distribution>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<DnsStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<Confidence>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trimmed: Option<Trimmed>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_time: Option<ServerTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer: Option<Transfer>,
+    /// The amount of sent request body bytes, if the sizes of the bodies
+    /// were varied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload: Option<Transfer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phases: Option<Phases>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<Delivery>,
+    /// The concurrency over time, if the run had a target rate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_rate: Option<RateReport>,
+    /// The latencies of all completed requests in nanoseconds, if they
+    /// are kept to test the significance of comparisons.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub samples: Option<Vec<u64>>,
+    /// The amount of responses with a Content-Encoding, if decompression
+    /// was disabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compressed: Option<usize>,
+    /// How the request bodies were framed, either 'chunked' or
+    /// 'content-length'; unset if no bodies were sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_encoding: Option<String>,
+    /// The amount of times the token has been refreshed, if a token
+    /// command was used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_refreshes: Option<usize>,
+    /// The share of requests sent over an already established connection
+    /// between 0 and 1; unset if new connections could not be detected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_reuse: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conditional: Option<Conditional>,
+    /// The TLS handshakes performed, if their resumption was controlled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_handshakes: Option<Handshakes>,
+    /// The users and requests routed to each URL, if the requests were
+    /// routed per user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sticky: Option<Vec<StickyTarget>>,
+    /// The resolution of the monotonic clock the latencies were measured
+    /// with, as probed at the start of the run.
+    #[serde(
+        default,
+        with = "crate::duration::nanos_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub clock_resolution: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiplexing: Option<Multiplexing>,
+}
+
+/// How the requests sent in multiplexed bursts were spread over the
+/// connections.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Multiplexing {
+    pub requests: usize,
+    pub connections: usize,
+}
+
+impl Multiplexing {
+    /// Returns the average amount of requests sent over a connection.
+    pub fn requests_per_connection(&self) -> f64 {
+        self.requests as f64 / self.connections.max(1) as f64
+    }
+}
+
+/// The responses to the requests sent with the ETag of a previous
+/// response in the If-None-Match header.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Conditional {
+    pub requests: usize,
+    /// The amount of requests answered with 304 Not Modified.
+    pub not_modified: usize,
+}
+
+impl Conditional {
+    /// Returns `None` if no conditional requests have been sent.
+    pub fn from_responses(res: &[Response]) -> Option<Self> {
+        let conditional: Vec<_> = res.iter().filter(|r| r.conditional).collect();
+        (!conditional.is_empty()).then(|| Self {
+            requests: conditional.len(),
+            not_modified: conditional
+                .iter()
+                .filter(|r| r.status == StatusCode::NOT_MODIFIED)
+                .count(),
+        })
+    }
+
+    /// Returns the share of conditional requests answered with 304
+    /// between 0 and 1.
+    pub fn hit_ratio(&self) -> f64 {
+        self.not_modified as f64 / self.requests as f64
+    }
+}
+
+/// The amount of most frequent error reasons which are kept.
+const TOP_ERROR_REASONS: usize = 5;
+
+/// A distinct reason requests failed for.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ErrorReason {
+    /// The kind of the failures, e.g. 'timeout'.
+    pub kind: String,
+    pub message: String,
+    pub count: u64,
+}
+
+impl ErrorReason {
+    /// Groups the failures by their kind and reason and returns the
+    /// most frequent reasons, ordered by their frequency.
+    pub fn top(failures: &[Failure]) -> Vec<Self> {
+        let counts = failures.iter().fold(BTreeMap::new(), |mut m, f| {
+            *m.entry((f.kind, f.reason.as_str())).or_insert(0) += 1;
+            m
+        });
+        let mut reasons: Vec<_> = counts
+            .into_iter()
+            .map(|((kind, message), count)| Self {
+                kind: kind.to_string(),
+                message: message.to_string(),
+                count,
+            })
+            .collect();
+        // The sort is stable, so that reasons with the same frequency
+        // remain ordered by their kind and message.
+        reasons.sort_by_key(|r| Reverse(r.count));
+        reasons.truncate(TOP_ERROR_REASONS);
+        reasons
+    }
+}
+
+/// The amount of most frequent redirect chains which are kept.
+const TOP_REDIRECT_CHAINS: usize = 5;
+
+/// A distinct chain of redirects followed by requests.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RedirectChain {
+    /// The status and URL of each response of the chain, e.g. '301
+    /// http://a/ -> 200 https://a/'.
+    pub chain: String,
+    pub count: u64,
+}
+
+impl RedirectChain {
+    /// Groups the redirected responses by their chain and returns the
+    /// most frequent chains, ordered by their frequency.
+    pub fn top(res: &[Response]) -> Vec<Self> {
+        let counts =
+            res.iter()
+                .filter(|r| !r.redirects.is_empty())
+                .fold(BTreeMap::new(), |mut m, r| {
+                    let chain: Vec<_> = r
+                        .redirects
+                        .iter()
+                        .map(|h| format!("{} {}", h.status.as_u16(), h.url))
+                        .collect();
+                    *m.entry(chain.join(" -> ")).or_insert(0) += 1;
+                    m
+                });
+        let mut chains: Vec<_> = counts
+            .into_iter()
+            .map(|(chain, count)| Self { chain, count })
+            .collect();
+        chains.sort_by_key(|c| Reverse(c.count));
+        chains.truncate(TOP_REDIRECT_CHAINS);
+        chains
+    }
+}
+
+/// Prints the error reasons as table of their frequencies.
+pub fn print_error_reasons(reasons: &[ErrorReason]) {
+    let pad = reasons
+        .iter()
+        .map(|r| r.count.to_string().len())
+        .max()
+        .unwrap_or_default();
+    for r in reasons {
+        println!("{:>pad$}  {}: {}", r.count, r.kind, r.message);
+    }
+}
+
+/// Statistics of the explicitly measured DNS lookups.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DnsStats {
+    #[serde(with = "crate::duration::nanos")]
+    pub avg: Duration,
+    /// The duration of the first lookup, which is the least likely to
+    /// be served from a cache.
+    #[serde(with = "crate::duration::nanos")]
+    pub first: Duration,
+    /// Whether subsequent lookups were answered considerably faster
+    /// than the first one, which indicates that they have been cached.
+    pub cached: bool,
+}
+
+/// The average and standard deviation of the latencies after removing
+/// the given percentage of the fastest and the slowest samples.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Trimmed {
+    /// The percentage trimmed from each end.
+    pub percent: f64,
+    #[serde(with = "crate::duration::nanos")]
+    pub avg: Duration,
+    #[serde(with = "crate::duration::nanos")]
+    pub stddev: Duration,
+}
+
+/// The average durations of the phases of a request; unset phases have
+/// not been measured.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Phases {
+    #[serde(default, with = "crate::duration::nanos_option")]
+    pub dns: Option<Duration>,
+    #[serde(default, with = "crate::duration::nanos_option")]
+    pub connect: Option<Duration>,
+    #[serde(default, with = "crate::duration::nanos_option")]
+    pub tls: Option<Duration>,
+    /// The time until the response headers have been received, which
+    /// includes connect and TLS, as they are not measured separately.
+    #[serde(default, with = "crate::duration::nanos_option")]
+    pub ttfb: Option<Duration>,
+    #[serde(default, with = "crate::duration::nanos_option")]
+    pub transfer: Option<Duration>,
+}
+
+/// The amount of transferred body bytes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Transfer {
+    pub bytes: u64,
+    /// The achieved bandwidth in bytes per second.
+    pub bandwidth: f64,
+}
+
+/// The times until the first and the last byte of the responses have
+/// been received and the transfer of the body in between, computed from
+/// the responses whose bodies have been read.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Delivery {
+    /// The amount of responses whose bodies have been read.
+    pub count: usize,
+    pub ttfb: Latencies,
+    pub ttlb: Latencies,
+    pub transfer: Latencies,
+}
+
+/// The processing time reported by the server compared to the latency
+/// observed by the client, computed from the responses which reported it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerTime {
+    /// The name of the header the processing time was read from.
+    pub header: String,
+    /// The amount of responses which reported a valid processing time.
+    pub count: usize,
+    pub client: Latencies,
+    pub server: Latencies,
+    /// The client latency minus the server time of each response, which
+    /// approximates the network and queueing overhead.
+    pub overhead: Latencies,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Latencies {
+    #[serde(with = "crate::duration::nanos")]
+    pub avg: Duration,
+    #[serde(with = "crate::duration::nanos")]
+    pub median: Duration,
+    #[serde(with = "crate::duration::nanos")]
+    pub p95: Duration,
+}
+
+/// Bootstrapped 95% confidence intervals of the latency metrics.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Confidence {
+    pub iterations: usize,
+    pub avg: Interval,
+    pub median: Interval,
+    pub p90: Interval,
+    pub p95: Interval,
+    pub p99: Interval,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Interval {
+    #[serde(with = "crate::duration::nanos")]
+    pub low: Duration,
+    #[serde(with = "crate::duration::nanos")]
+    pub high: Duration,
+}
+
+/// Shape parameters of the latency distribution.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Distribution {
+    pub skewness: f64,
+    /// The excess kurtosis, which is 0 for a normal distribution.
+    pub kurtosis: f64,
+    /// The skewness of the logarithmized samples, which is close
+    /// to 0 for a log-normal distribution.
+    pub log_skewness: f64,
+}
+
+impl Stats {
+    /// Computes the statistics from the given responses and failures.
+    /// Returns `None` if `res` is empty.
+    pub fn from_responses(res: &[Response], failures: &[Failure]) -> Option<Self> {
+        let first = res.iter().min_by_key(|r| r.timestamp)?;
+
+        let n = res.len() as f64;
+
+        let min = res.iter().min_by_key(|r| r.took)?;
+        let max = res.iter().max_by_key(|r| r.took)?;
+
+        let mut times: Vec<_> = res.iter().map(|r| r.took).collect();
+        times.sort();
+        let sum: Duration = times.iter().sum();
+        let avg = sum.as_nanos() as f64 / n;
+        let sd = (times
+            .iter()
+            .map(|v| v.as_nanos() as f64)
+            .fold(0f64, |acc, v| acc + (v - avg).powf(2f64))
+            / n)
+            .sqrt();
+
+        let statuses = res.iter().fold(BTreeMap::new(), |mut m, resp| {
+            m.entry(resp.status.as_u16())
+                .and_modify(|v| *v += 1)
+                .or_insert(1);
+            m
+        });
+
+        let methods = res
+            .iter()
+            .map(|r| &r.method)
+            .chain(failures.iter().map(|f| &f.method))
+            .fold(BTreeMap::new(), |mut m, method| {
+                m.entry(method.to_string())
+                    .and_modify(|v| *v += 1)
+                    .or_insert(1);
+                m
+            });
+
+        let mut by_method = res.iter().fold(BTreeMap::new(), |mut m, r| {
+            m.entry(r.method.to_string())
+                .or_insert_with(Vec::new)
+                .push(r.took);
+            m
+        });
+        let method_latencies = if by_method.len() > 1 {
+            by_method
+                .iter_mut()
+                .map(|(method, times)| {
+                    times.sort();
+                    (method.clone(), Latencies::from_times(times))
+                })
+                .collect()
+        } else {
+            BTreeMap::new()
+        };
+
+        let protocols = res.iter().fold(BTreeMap::new(), |mut m, r| {
+            m.entry(format!("{:?}", r.version))
+                .and_modify(|v| *v += 1)
+                .or_insert(1);
+            m
+        });
+
+        let errors = failures.iter().fold(BTreeMap::new(), |mut m, f| {
+            m.entry(f.kind.to_string())
+                .and_modify(|v| *v += 1)
+                .or_insert(1);
+            m
+        });
+
+        Some(Self {
+            count: res.len(),
+            failed: res.iter().filter(|r| !r.is_success()).count() + failures.len(),
+            injected: res.iter().filter(|r| r.injected).count(),
+            http_errors: res
+                .iter()
+                .filter(|r| r.status.is_client_error() || r.status.is_server_error())
+                .count(),
+            truncated: res.iter().filter(|r| r.truncated).count(),
+            min: min.took,
+            min_status: min.status,
+            max: max.took,
+            max_status: max.status,
+            first: first.took,
+            first_status: first.status,
+            avg: Duration::from_nanos(avg as u64),
+            median: get_median(&times),
+            stddev: Duration::from_nanos(sd as u64),
+            p90: get_nth_percentile(&times, 0.90),
+            p95: get_nth_percentile(&times, 0.95),
+            p99: get_nth_percentile(&times, 0.99),
+            total: sum,
+            statuses,
+            methods,
+            method_latencies,
+            errors,
+            error_reasons: ErrorReason::top(failures),
+            redirect_chains: RedirectChain::top(res),
+            protocols,
+            distribution: None,
+            dns: DnsStats::from_responses(res),
+            confidence: None,
+            trimmed: None,
+            server_time: None,
+            transfer: None,
+            upload: None,
+            phases: None,
+            delivery: None,
+            target_rate: None,
+            samples: None,
+            compressed: None,
+            body_encoding: None,
+            token_refreshes: None,
+            connection_reuse: None,
+            conditional: Conditional::from_responses(res),
+            tls_handshakes: None,
+            sticky: None,
+            clock_resolution: None,
+            multiplexing: None,
+        })
+    }
+
+    /// Prints the statistics; a warning is printed if less than
+    /// `min_samples` samples were collected, because percentiles are
+    /// not meaningful for small sample sizes.
+    pub fn print(&self, min_samples: usize, unit: Option<DurationUnit>) {
+        let fmt = |d| format_duration(d).in_unit(unit);
+        println!(
+            "Results of {} probes:\n\
+            \n\
+            Min:        {:>10.4}  ({})\n\
+            Max:        {:>10.4}  ({})\n\
+            First:      {:>10.4}  ({})\n\
+            Average:    {:>10.4}{}\n\
+            Median:     {:>10.4}{}\n\
+            Std. Dev.:  {:>10.4}\
+            ",
+            self.total_count(),
+            fmt(self.min),
+            self.min_status,
+            fmt(self.max),
+            self.max_status,
+            fmt(self.first),
+            self.first_status,
+            fmt(self.avg),
+            self.interval(unit, |c| c.avg),
+            fmt(self.median),
+            self.interval(unit, |c| c.median),
+            fmt(self.stddev),
+        );
+
+        if let Some(t) = &self.trimmed {
+            println!(
+                "Trim. Avg.: {:>10.4}  ({}% trimmed)\n\
+                Trim. SD.:  {:>10.4}  ({}% trimmed)\
+                ",
+                fmt(t.avg),
+                t.percent,
+                fmt(t.stddev),
+                t.percent,
+            );
+        }
+
+        if let Some(d) = &self.distribution {
+            println!(
+                "Skewness:   {:>10.4}\n\
+                Kurtosis:   {:>10.4}\n\
+                Shape:      {:>10}\
+                ",
+                d.skewness,
+                d.kurtosis,
+                d.classify(),
+            );
+        }
+
+        println!(
+            "90th %ile.: {:>10.4}{}\n\
+            95th %ile.: {:>10.4}{}\n\
+            99th %ile.: {:>10.4}{}\n\
+            Total:      {:>10.4}\
+            ",
+            fmt(self.p90),
+            self.interval(unit, |c| c.p90),
+            fmt(self.p95),
+            self.interval(unit, |c| c.p95),
+            fmt(self.p99),
+            self.interval(unit, |c| c.p99),
+            fmt(self.total),
+        );
+
+        if self.count < min_samples {
+            println!(
+                "warning: the percentiles are unreliable with only {} samples; \
+                at least {min_samples} are recommended",
+                self.count
+            );
+        }
+
+        if let Some(dns) = &self.dns {
+            println!(
+                "DNS Lookup: {:>10.4}  (first {:.4}{})",
+                fmt(dns.avg),
+                fmt(dns.first),
+                if dns.cached { ", cached" } else { "" },
+            );
+        }
+
+        if let Some(t) = &self.transfer {
+            println!(
+                "Transfer:   {:>10.4}  ({:.4}/s)",
+                format_bytes(t.bytes as f64),
+                format_bytes(t.bandwidth),
+            );
+        }
+
+        if let Some(t) = &self.upload {
+            println!(
+                "Upload:     {:>10.4}  ({:.4}/s)",
+                format_bytes(t.bytes as f64),
+                format_bytes(t.bandwidth),
+            );
+        }
+
+        if let Some(compressed) = self.compressed {
+            println!("Compressed: {compressed:>10}  (responses, not decompressed)");
+        }
+
+        if let (1, Some(protocol)) = (self.protocols.len(), self.protocols.keys().next()) {
+            println!("Protocol:   {protocol:>10}");
+        }
+
+        if let Some(encoding) = &self.body_encoding {
+            println!("Body:       {encoding:>10}");
+        }
+
+        if let Some(reuse) = self.connection_reuse {
+            println!("Reused:     {:>9.2}%  (connections)", reuse * 100f64);
+        }
+
+        if let Some(m) = self.multiplexing {
+            println!(
+                "Multiplexed:{:>10.2}  (requests per connection, {} connections)",
+                m.requests_per_connection(),
+                m.connections
+            );
+        }
+
+        if let Some(h) = self.tls_handshakes {
+            println!("Handshakes: {:>10}  ({} resumed)", h.total, h.resumed);
+        }
+
+        if let Some(c) = &self.conditional {
+            println!(
+                "304 Hits:   {:>9.2}%  (of {} conditional requests)",
+                c.hit_ratio() * 100f64,
+                c.requests
+            );
+        }
+
+        if let Some(refreshes) = self.token_refreshes {
+            println!("Refreshes:  {refreshes:>10}  (token)");
+        }
+
+        if let Some(p) = &self.phases {
+            p.print(unit);
+        }
+
+        if let Some(t) = &self.server_time {
+            println!(
+                "\nServer time of {} responses ({}):\n\
+                \n            {:>12}  {:>12}  {:>12}\n\
+                Average:    {:>10.4}  {:>10.4}  {:>10.4}\n\
+                Median:     {:>10.4}  {:>10.4}  {:>10.4}\n\
+                95th %ile.: {:>10.4}  {:>10.4}  {:>10.4}\
+                ",
+                t.count,
+                t.header,
+                "Client",
+                "Server",
+                "Overhead",
+                fmt(t.client.avg),
+                fmt(t.server.avg),
+                fmt(t.overhead.avg),
+                fmt(t.client.median),
+                fmt(t.server.median),
+                fmt(t.overhead.median),
+                fmt(t.client.p95),
+                fmt(t.server.p95),
+                fmt(t.overhead.p95),
+            );
+        }
+
+        if let Some(d) = &self.delivery {
+            println!(
+                "\nDelivery of {} responses:\n\
+                \n            {:>12}  {:>12}  {:>12}\n\
+                Average:    {:>10.4}  {:>10.4}  {:>10.4}\n\
+                Median:     {:>10.4}  {:>10.4}  {:>10.4}\n\
+                95th %ile.: {:>10.4}  {:>10.4}  {:>10.4}\
+                ",
+                d.count,
+                "TTFB",
+                "TTLB",
+                "Transfer",
+                fmt(d.ttfb.avg),
+                fmt(d.ttlb.avg),
+                fmt(d.transfer.avg),
+                fmt(d.ttfb.median),
+                fmt(d.ttlb.median),
+                fmt(d.transfer.median),
+                fmt(d.ttfb.p95),
+                fmt(d.ttlb.p95),
+                fmt(d.transfer.p95),
+            );
+        }
+
+        println!();
+
+        self.print_binned_statuscodes();
+        self.print_failures();
+
+        if !self.error_reasons.is_empty() {
+            println!("\nTop errors:");
+            print_error_reasons(&self.error_reasons);
+        }
+
+        if !self.redirect_chains.is_empty() {
+            println!("\nRedirect chains:");
+            let pad = self
+                .redirect_chains
+                .iter()
+                .map(|c| c.count.to_string().len())
+                .max()
+                .unwrap_or_default();
+            for c in &self.redirect_chains {
+                println!("{:>pad$}  {}", c.count, c.chain);
+            }
+        }
+
+        if self.injected > 0 {
+            println!(
+                "\nInjected:   {} responses have been marked as failed (synthetic)",
+                self.injected
+            );
+        }
+
+        if self.truncated > 0 {
+            println!(
+                "\nTruncated:  {} response bodies exceeded the maximum size and have not been \
+                read completely",
+                self.truncated
+            );
+        }
+
+        if self.methods.len() > 1 {
+            println!();
+            print_binned(&self.methods, self.total_count());
+        }
+
+        if !self.method_latencies.is_empty() {
+            println!(
+                "\n{:<12}{:>12}  {:>12}  {:>12}",
+                "Method", "Average", "Median", "95th %ile."
+            );
+            for (method, l) in &self.method_latencies {
+                println!(
+                    "{:<12}{:>10.4}  {:>10.4}  {:>10.4}",
+                    format!("{method}:"),
+                    fmt(l.avg),
+                    fmt(l.median),
+                    fmt(l.p95),
+                );
+            }
+        }
+
+        if self.protocols.len() > 1 {
+            println!();
+            print_binned(&self.protocols, self.count);
+        }
+
+        if let Some(targets) = &self.sticky {
+            let users: usize = targets.iter().map(|t| t.users).sum();
+            println!("\nSticky sessions of {users} users:");
+            let pad = users.to_string().len();
+            for t in targets {
+                let prct = t.users as f64 / users as f64 * 100f64;
+                println!(
+                    "{}:  {:>pad$} users ({prct:>5.2}%), {} requests",
+                    t.url, t.users, t.requests
+                );
+            }
+        }
+
+        if let Some(rate) = &self.target_rate {
+            rate.print();
+        }
+    }
+
+    /// Prints the latencies and the status codes as GitHub-flavored
+    /// Markdown tables, e.g. to paste them into an issue.
+    pub fn print_markdown(&self, unit: Option<DurationUnit>) {
+        let fmt = |d| format!("{:.4}", format_duration(d).in_unit(unit));
+        let rows = [
+            ("Requests", self.total_count().to_string()),
+            ("Min", fmt(self.min)),
+            ("Max", fmt(self.max)),
+            ("First", fmt(self.first)),
+            ("Average", fmt(self.avg)),
+            ("Median", fmt(self.median)),
+            ("Std. Dev.", fmt(self.stddev)),
+            ("90th %ile.", fmt(self.p90)),
+            ("95th %ile.", fmt(self.p95)),
+            ("99th %ile.", fmt(self.p99)),
+            ("Total", fmt(self.total)),
+        ];
+
+        println!("| Metric | Value |\n|---|---:|");
+        for (metric, value) in rows {
+            println!("| {metric} | {value} |");
+        }
+
+        let all = self.total_count() as f32;
+        println!("\n| Status | Count | Share |\n|---|---:|---:|");
+        for (&status_code, &n) in &self.statuses {
+            let prct = n as f32 / all * 100f32;
+            println!("| {} | {n} | {prct:.2}% |", display_status(status_code));
+        }
+        for (kind, &n) in &self.errors {
+            let prct = n as f32 / all * 100f32;
+            println!("| {kind} | {n} | {prct:.2}% |");
+        }
+    }
+
+    /// Formats the selected confidence interval, if computed, as suffix
+    /// of the printed metric.
+    fn interval(
+        &self,
+        unit: Option<DurationUnit>,
+        select: impl Fn(&Confidence) -> Interval,
+    ) -> String {
+        self.confidence
+            .as_ref()
+            .map(|c| {
+                let i = select(c);
+                format!(
+                    "  (95% CI {:.4} .. {:.4})",
+                    format_duration(i.low).in_unit(unit),
+                    format_duration(i.high).in_unit(unit)
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the kept latencies, if any.
+    pub fn samples(&self) -> Option<Vec<Duration>> {
+        let samples = self.samples.as_ref()?;
+        Some(samples.iter().copied().map(Duration::from_nanos).collect())
+    }
+
+    /// Returns the total amount of sent requests.
+    pub fn total_count(&self) -> usize {
+        self.count + self.errors.values().sum::<u64>() as usize
+    }
+
+    fn print_binned_statuscodes(&self) {
+        let all = self.total_count() as f32;
+
+        let pad = self
+            .statuses
+            .values()
+            .chain(self.errors.values())
+            .max()
+            .map(|v| v.to_string().len())
+            .unwrap_or_default();
+
+        for (&status_code, &n) in &self.statuses {
+            let prct = n as f32 / all * 100f32;
+            let status_code = display_status(status_code);
+            println!("{status_code}:  {n:>pad$} ({prct:>5.2}%)");
+        }
+
+        for (kind, &n) in &self.errors {
+            let prct = n as f32 / all * 100f32;
+            println!("{kind}:  {n:>pad$} ({prct:>5.2}%)");
+        }
+    }
+
+    /// Prints the requests which failed without a response apart from
+    /// the responses with an error status, each along with its kinds or
+    /// status classes.
+    fn print_failures(&self) {
+        let transport: u64 = self.errors.values().sum();
+        if transport == 0 && self.http_errors == 0 {
+            return;
+        }
+
+        let classes = self
+            .statuses
+            .iter()
+            .filter(|(&status, _)| (400..600).contains(&status))
+            .fold(BTreeMap::new(), |mut m, (&status, &n)| {
+                *m.entry(status / 100).or_insert(0) += n;
+                m
+            });
+        let note = |counts: Vec<String>| {
+            if counts.is_empty() {
+                String::new()
+            } else {
+                format!("  ({})", counts.join(", "))
+            }
+        };
+        let kinds = note(
+            self.errors
+                .iter()
+                .map(|(k, n)| format!("{k} {n}"))
+                .collect(),
+        );
+        let classes = note(classes.iter().map(|(c, n)| format!("{c}xx {n}")).collect());
+        println!(
+            "\nTransport:  {transport:>10}{kinds}\n\
+            HTTP errors:{:>10}{classes}",
+            self.http_errors
+        );
+    }
+}
+
+impl Phases {
+    /// The width of the bars of the printed waterfall.
+    const BAR_WIDTH: usize = 40;
+
+    pub fn from_responses(res: &[Response]) -> Self {
+        let avg = |select: fn(&Response) -> Option<Duration>| {
+            let times: Vec<_> = res.iter().filter_map(select).collect();
+            (!times.is_empty()).then(|| times.iter().sum::<Duration>() / times.len() as u32)
+        };
+
+        Self {
+            dns: avg(|r| r.dns),
+            connect: None,
+            tls: None,
+            ttfb: avg(|r| r.ttfb),
+            transfer: avg(Response::transfer),
+        }
+    }
+
+    /// Prints the phases as waterfall, in which each phase starts after
+    /// the previous one ended.
+    fn print(&self, unit: Option<DurationUnit>) {
+        let phases = [
+            ("DNS", self.dns),
+            ("Connect", self.connect),
+            ("TLS", self.tls),
+            ("TTFB", self.ttfb),
+            ("Transfer", self.transfer),
+        ];
+        let total: Duration = phases.iter().filter_map(|(_, d)| *d).sum();
+        let total = total.as_secs_f64();
+
+        println!("\nPhases:");
+        let mut start = 0f64;
+        for (name, d) in phases {
+            let name = format!("{name}:");
+            let Some(d) = d else {
+                println!("{name:<12}{:>12}", "n/a");
+                continue;
+            };
+
+            let share = if total > 0f64 {
+                d.as_secs_f64() / total
+            } else {
+                0f64
+            };
+            let offset = (start * Self::BAR_WIDTH as f64).round() as usize;
+            let len = ((share * Self::BAR_WIDTH as f64).round() as usize).max(1);
+            println!(
+                "{name:<12}{:>10.4}  {:>6.2}%  {}{}",
+                format_duration(d).in_unit(unit),
+                share * 100f64,
+                " ".repeat(offset),
+                "#".repeat(len),
+            );
+            start += share;
+        }
+        if self.ttfb.is_some() && self.connect.is_none() && self.tls.is_none() {
+            println!("(TTFB includes connect and TLS, which are not measured yet)");
+        }
+    }
+}
+
+impl ServerTime {
+    /// Returns `None` if none of the responses reported a server time.
+    pub fn from_responses(res: &[Response], header: &str) -> Option<Self> {
+        let pairs: Vec<_> = res
+            .iter()
+            .filter_map(|r| Some((r.took, r.server_time?)))
+            .collect();
+        if pairs.is_empty() {
+            return None;
+        }
+
+        let latencies = |select: fn(&(Duration, Duration)) -> Duration| {
+            let mut times: Vec<_> = pairs.iter().map(select).collect();
+            times.sort();
+            Latencies::from_times(&times)
+        };
+
+        Some(Self {
+            header: header.to_string(),
+            count: pairs.len(),
+            client: latencies(|(client, _)| *client),
+            server: latencies(|(_, server)| *server),
+            overhead: latencies(|(client, server)| client.saturating_sub(*server)),
+        })
+    }
+}
+
+impl Delivery {
+    /// Returns `None` if none of the response bodies have been read.
+    pub fn from_responses(res: &[Response]) -> Option<Self> {
+        let pairs: Vec<_> = res
+            .iter()
+            .filter_map(|r| Some((r.ttfb?, r.ttlb?)))
+            .collect();
+        if pairs.is_empty() {
+            return None;
+        }
+
+        let latencies = |select: fn(&(Duration, Duration)) -> Duration| {
+            let mut times: Vec<_> = pairs.iter().map(select).collect();
+            times.sort();
+            Latencies::from_times(&times)
+        };
+
+        Some(Self {
+            count: pairs.len(),
+            ttfb: latencies(|(ttfb, _)| *ttfb),
+            ttlb: latencies(|(_, ttlb)| *ttlb),
+            transfer: latencies(|(ttfb, ttlb)| ttlb.saturating_sub(*ttfb)),
+        })
+    }
+}
+
+impl Latencies {
+    /// Computes the metrics from the given non-empty, sorted latencies.
+    fn from_times(times: &[Duration]) -> Self {
+        Self {
+            avg: times.iter().sum::<Duration>() / times.len() as u32,
+            median: get_median(times),
+            p95: get_nth_percentile(times, 0.95),
+        }
+    }
+}
+
+impl DnsStats {
+    /// Lookups taking less than this fraction of the first lookup are
+    /// considered to be cached.
+    const CACHED_FACTOR: f64 = 0.5;
+
+    fn from_responses(res: &[Response]) -> Option<Self> {
+        let mut lookups: Vec<_> = res
+            .iter()
+            .filter_map(|r| Some((r.timestamp, r.dns?)))
+            .collect();
+        lookups.sort_by_key(|(timestamp, _)| *timestamp);
+
+        let (_, first) = *lookups.first()?;
+        let sum: Duration = lookups.iter().map(|(_, d)| *d).sum();
+        let avg = sum / lookups.len() as u32;
+
+        let rest = &lookups[1..];
+        let cached = !rest.is_empty() && {
+            let rest_avg = rest.iter().map(|(_, d)| *d).sum::<Duration>() / rest.len() as u32;
+            rest_avg.as_secs_f64() < first.as_secs_f64() * Self::CACHED_FACTOR
+        };
+
+        Some(Self { avg, first, cached })
+    }
+}
+
+impl Distribution {
+    /// The minimum amount of samples required to give the shape
+    /// parameters any meaning.
+    pub const MIN_SAMPLES: usize = 30;
+
+    /// Computes the shape parameters of the given samples. Returns `None`
+    /// if there are less than [`Self::MIN_SAMPLES`] samples or if all
+    /// samples are equal.
+    pub fn from_times(times: &[Duration]) -> Option<Self> {
+        if times.len() < Self::MIN_SAMPLES {
+            return None;
+        }
+
+        let values: Vec<_> = times.iter().map(|t| t.as_nanos() as f64).collect();
+        let (skewness, kurtosis) = moments(&values)?;
+
+        let log_values: Vec<_> = values.iter().map(|v| v.max(1f64).ln()).collect();
+        let (log_skewness, _) = moments(&log_values)?;
+
+        Some(Self {
+            skewness,
+            kurtosis,
+            log_skewness,
+        })
+    }
+
+    /// Returns a rough classification of the distribution's shape.
+    pub fn classify(&self) -> &'static str {
+        if self.skewness.abs() < 0.5 && self.kurtosis.abs() < 1f64 {
+            "normal"
+        } else if self.skewness > 0.5 && self.log_skewness.abs() < 0.5 {
+            "log-normal"
+        } else if self.kurtosis > 3f64 {
+            "heavy-tailed"
+        } else if self.kurtosis < -1f64 {
+            "light-tailed"
+        } else {
+            "skewed"
+        }
+    }
+}
+
+/// Returns the skewness and excess kurtosis of the given values or
+/// `None` if the values have no variance.
+fn moments(values: &[f64]) -> Option<(f64, f64)> {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+
+    let (m2, m3, m4) = values.iter().fold((0f64, 0f64, 0f64), |(m2, m3, m4), v| {
+        let d = v - mean;
+        (m2 + d.powi(2), m3 + d.powi(3), m4 + d.powi(4))
+    });
+    let (m2, m3, m4) = (m2 / n, m3 / n, m4 / n);
+
+    if m2 == 0f64 {
+        return None;
+    }
+
+    Some((m3 / m2.powf(1.5), m4 / m2.powi(2) - 3f64))
+}
+
+impl Trimmed {
+    /// Computes the trimmed statistics of the given sorted latencies.
+    /// Returns `None` if no samples remain after trimming.
+    pub fn from_times(times: &[Duration], percent: f64) -> Option<Self> {
+        let k = (times.len() as f64 * percent / 100f64) as usize;
+        let rest = times.get(k..times.len().checked_sub(k)?)?;
+        if rest.is_empty() {
+            return None;
+        }
+
+        let n = rest.len() as f64;
+        let avg = rest.iter().map(|v| v.as_nanos() as f64).sum::<f64>() / n;
+        let sd = (rest
+            .iter()
+            .map(|v| (v.as_nanos() as f64 - avg).powf(2f64))
+            .sum::<f64>()
+            / n)
+            .sqrt();
+
+        Some(Self {
+            percent,
+            avg: Duration::from_nanos(avg as u64),
+            stddev: Duration::from_nanos(sd as u64),
+        })
+    }
+}
+
+impl Confidence {
+    /// Resamples the given sorted latencies with replacement `iterations`
+    /// times and takes the 2.5th and 97.5th percentiles of the metrics
+    /// over all resamples. Each resample uses its own RNG derived from
+    /// `seed`, so that the result is reproducible although the resamples
+    /// are computed in parallel. Returns `None` if `times` is empty.
+    pub fn bootstrap(times: &[Duration], iterations: usize, seed: u64) -> Option<Self> {
+        if times.is_empty() || iterations == 0 {
+            return None;
+        }
+
+        let samples: Vec<[Duration; 5]> = (0..iterations)
+            .into_par_iter()
+            .map(|k| {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(k as u64));
+                let mut sample: Vec<_> = (0..times.len())
+                    .map(|_| times[rng.gen_range(0..times.len())])
+                    .collect();
+                sample.sort();
+                let sum: Duration = sample.iter().sum();
+                [
+                    sum / sample.len() as u32,
+                    get_median(&sample),
+                    get_nth_percentile(&sample, 0.90),
+                    get_nth_percentile(&sample, 0.95),
+                    get_nth_percentile(&sample, 0.99),
+                ]
+            })
+            .collect();
+
+        let interval = |metric: usize| {
+            let mut values: Vec<_> = samples.iter().map(|s| s[metric]).collect();
+            values.sort();
+            Interval {
+                low: get_nth_percentile(&values, 0.025),
+                high: get_nth_percentile(&values, 0.975),
+            }
+        };
+
+        Some(Self {
+            iterations,
+            avg: interval(0),
+            median: interval(1),
+            p90: interval(2),
+            p95: interval(3),
+            p99: interval(4),
+        })
+    }
+}
+
+pub fn display_status(code: u16) -> String {
+    StatusCode::from_u16(code)
+        .map(|s| s.to_string())
+        .unwrap_or_else(|_| code.to_string())
+}
+
+/// Prints the counts as share of `all`, one per line.
+fn print_binned(counts: &BTreeMap<String, u64>, all: usize) {
+    let pad_name = counts.keys().map(|k| k.len()).max().unwrap_or_default();
+    let pad = counts
+        .values()
+        .max()
+        .map(|v| v.to_string().len())
+        .unwrap_or_default();
+
+    for (name, &n) in counts {
+        let prct = n as f32 / all as f32 * 100f32;
+        let name = format!("{name}:");
+        println!("{name:<0$}  {n:>pad$} ({prct:>5.2}%)", pad_name + 1);
+    }
+}
+
+pub(crate) fn get_median(times: &[Duration]) -> Duration {
+    if times.len() % 2 == 1 {
+        let middle = times.len().div_ceil(2) - 1;
+        return times[middle];
+    }
+
+    let middle_l = (times.len() / 2) - 1;
+    let middle_r = times.len() / 2;
+
+    (times[middle_l] + times[middle_r]) / 2
+}
+
+pub(crate) fn get_nth_percentile(times: &[Duration], percentile: f64) -> Duration {
+    let el = times.len() as f64 * percentile;
+    let el_trunc = el as isize - 1;
+    if el_trunc < 0 {
+        return times[0];
+    }
+
+    if el_trunc as usize + 1 >= times.len() {
+        return times[el_trunc as usize];
+    }
+
+    let el_a = times[el_trunc as usize];
+    let el_b = times[el_trunc as usize + 1];
+
+    let el_fract_b = el - 1f64 - el_trunc as f64;
+    let el_fract_a = 1f64 - el_fract_b;
+
+    let res = (el_a.as_nanos() as f64 * el_fract_a + el_b.as_nanos() as f64 * el_fract_b).round();
+
+    Duration::from_nanos(res as u64)
+}
+
+mod status_code {
+    use reqwest::StatusCode;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &StatusCode, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_u16(v.as_u16())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<StatusCode, D::Error> {
+        let v = u16::deserialize(d)?;
+        StatusCode::from_u16(v).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{request::FailureKind, test_util};
+    use chrono::Utc;
+    use reqwest::{Method, Version};
+
+    fn response(took: u64, server_time: Option<u64>) -> Response {
+        Response {
+            server_time: server_time.map(Duration::from_millis),
+            ..test_util::response(took)
+        }
+    }
+
+    #[test]
+    fn error_reasons() {
+        // The messages differ by the URL, by which the reasons are not
+        // grouped.
+        let failure = |kind, url: &str, reason: &str| Failure {
+            index: 0,
+            method: Method::GET,
+            kind,
+            message: format!("error sending request for url ({url}): {reason}"),
+            reason: reason.into(),
+            took: Duration::ZERO,
+            timestamp: Utc::now(),
+        };
+        let mut failures = vec![
+            failure(FailureKind::Connect, "http://a/", "connection refused"),
+            failure(FailureKind::Timeout, "http://a/?1", "operation timed out"),
+            failure(FailureKind::Timeout, "http://a/?2", "operation timed out"),
+            failure(FailureKind::Connect, "http://b/", "connection refused"),
+            failure(FailureKind::Timeout, "http://a/?3", "operation timed out"),
+            failure(FailureKind::Dns, "http://c/", "no address found"),
+        ];
+
+        let reasons = ErrorReason::top(&failures);
+        let summary: Vec<_> = reasons
+            .iter()
+            .map(|r| (r.kind.as_str(), r.message.as_str(), r.count))
+            .collect();
+        assert_eq!(
+            vec![
+                ("timeout", "operation timed out", 3),
+                ("connect", "connection refused", 2),
+                ("dns", "no address found", 1),
+            ],
+            summary
+        );
+
+        failures.extend(
+            (0..10).map(|i| failure(FailureKind::Other, "http://a/", &format!("error {i}"))),
+        );
+        assert_eq!(TOP_ERROR_REASONS, ErrorReason::top(&failures).len());
+        assert!(ErrorReason::top(&[]).is_empty());
+    }
+
+    #[test]
+    fn http_errors() {
+        let res = [200, 404, 503, 500].map(|status| Response {
+            status: StatusCode::from_u16(status).unwrap(),
+            ..response(10, None)
+        });
+        let failures = [FailureKind::Connect, FailureKind::Tls].map(|kind| Failure {
+            index: 0,
+            method: Method::GET,
+            kind,
+            message: String::new(),
+            reason: String::new(),
+            took: Duration::ZERO,
+            timestamp: Utc::now(),
+        });
+
+        let stats = Stats::from_responses(&res, &failures).unwrap();
+        assert_eq!(5, stats.failed);
+        assert_eq!(3, stats.http_errors);
+        assert_eq!(Some(&1), stats.errors.get("connect"));
+        assert_eq!(Some(&1), stats.errors.get("tls"));
+    }
+
+    #[test]
+    fn server_time() {
+        let res = [
+            response(10, Some(4)),
+            response(20, Some(12)),
+            response(30, None),
+        ];
+
+        let t = ServerTime::from_responses(&res, "x-response-time").unwrap();
+        assert_eq!(2, t.count);
+        assert_eq!(Duration::from_millis(15), t.client.avg);
+        assert_eq!(Duration::from_millis(8), t.server.median);
+        assert_eq!(Duration::from_millis(7), t.overhead.avg);
+
+        assert!(ServerTime::from_responses(&res[2..], "x-response-time").is_none());
+    }
+
+    #[test]
+    fn phases() {
+        let res = [10, 20].map(|ms| Response {
+            ttfb: Some(Duration::from_millis(ms)),
+            dns: Some(Duration::from_millis(2)),
+            ..response(ms + 10, None)
+        });
+        let p = Phases::from_responses(&res);
+        assert_eq!(Some(Duration::from_millis(2)), p.dns);
+        assert_eq!(Some(Duration::from_millis(15)), p.ttfb);
+        assert_eq!(None, p.connect);
+        // The bodies have not been read.
+        assert_eq!(None, p.transfer);
+    }
+
+    #[test]
+    fn delivery() {
+        let res = [(10, 40), (20, 30)].map(|(ttfb, ttlb)| Response {
+            ttfb: Some(Duration::from_millis(ttfb)),
+            ttlb: Some(Duration::from_millis(ttlb)),
+            body_size: Some(1024),
+            ..response(ttlb, None)
+        });
+        let d = Delivery::from_responses(&res).unwrap();
+        assert_eq!(2, d.count);
+        assert_eq!(Duration::from_millis(15), d.ttfb.avg);
+        assert_eq!(Duration::from_millis(35), d.ttlb.avg);
+        assert_eq!(Duration::from_millis(20), d.transfer.avg);
+        assert_eq!(
+            Some(Duration::from_millis(20)),
+            Phases::from_responses(&res).transfer
+        );
+
+        // The bodies have not been read.
+        assert!(Delivery::from_responses(&[response(10, None)]).is_none());
+    }
+
+    #[test]
+    fn method_latencies() {
+        let res =
+            [(Method::GET, 10), (Method::POST, 30), (Method::GET, 20)].map(|(method, took)| {
+                Response {
+                    method,
+                    ..response(took, None)
+                }
+            });
+        let stats = Stats::from_responses(&res, &[]).unwrap();
+        assert_eq!(2, stats.method_latencies.len());
+        assert_eq!(Duration::from_millis(15), stats.method_latencies["GET"].avg);
+        assert_eq!(
+            Duration::from_millis(30),
+            stats.method_latencies["POST"].median
+        );
+
+        let stats = Stats::from_responses(&res[..1], &[]).unwrap();
+        assert!(stats.method_latencies.is_empty());
+    }
+
+    #[test]
+    fn protocols() {
+        let res = [Version::HTTP_11, Version::HTTP_2, Version::HTTP_2].map(|version| Response {
+            version,
+            ..response(10, None)
+        });
+        let stats = Stats::from_responses(&res, &[]).unwrap();
+        assert_eq!(
+            vec![("HTTP/1.1", 1), ("HTTP/2.0", 2)],
+            stats
+                .protocols
+                .iter()
+                .map(|(k, &v)| (k.as_str(), v))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn trimmed() {
+        let mut times: Vec<_> = (1..=9).map(|_| Duration::from_millis(10)).collect();
+        times.push(Duration::from_secs(10));
+
+        let t = Trimmed::from_times(&times, 10f64).unwrap();
+        assert_eq!(Duration::from_millis(10), t.avg);
+        assert_eq!(Duration::ZERO, t.stddev);
+
+        assert!(Trimmed::from_times(&times, 50f64).is_none());
+        assert!(Trimmed::from_times(&[], 10f64).is_none());
+    }
+
+    #[test]
+    fn bootstrap() {
+        let times: Vec<_> = (1..=100).map(Duration::from_millis).collect();
+        let c = Confidence::bootstrap(&times, 200, 7).unwrap();
+
+        let avg = Duration::from_micros(50_500);
+        assert!(c.avg.low < avg && avg < c.avg.high);
+        assert!(c.p90.low <= c.p90.high && c.p90.high <= Duration::from_millis(100));
+
+        let again = Confidence::bootstrap(&times, 200, 7).unwrap();
+        assert_eq!(c.median.low, again.median.low);
+        assert_eq!(c.p99.high, again.p99.high);
+
+        assert!(Confidence::bootstrap(&[], 200, 7).is_none());
+    }
+
+    #[test]
+    fn nth_percentile() {
+        let times: Vec<_> = (1..=10).map(Duration::from_millis).collect();
+        assert_eq!(Duration::from_millis(9), get_nth_percentile(&times, 0.9));
+        assert_eq!(
+            Duration::from_micros(9_500),
+            get_nth_percentile(&times, 0.95)
+        );
+        assert_eq!(Duration::from_millis(1), get_nth_percentile(&times, 0.01));
+
+        let times: Vec<_> = (1..=30).map(Duration::from_millis).collect();
+        assert!(get_nth_percentile(&times, 0.99) <= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn distribution() {
+        let times: Vec<_> = (1..Distribution::MIN_SAMPLES as u64)
+            .map(Duration::from_millis)
+            .collect();
+        assert!(Distribution::from_times(&times).is_none());
+
+        let times = vec![Duration::from_millis(10); 100];
+        assert!(Distribution::from_times(&times).is_none());
+
+        let times: Vec<_> = (1..=100).map(Duration::from_millis).collect();
+        let d = Distribution::from_times(&times).unwrap();
+        assert!(d.skewness.abs() < 1e-9);
+        assert!((d.kurtosis + 1.2).abs() < 0.01);
+        assert_eq!("light-tailed", d.classify());
+
+        let times: Vec<_> = (0..100)
+            .map(|i| Duration::from_millis(if i % 20 == 0 { 1000 } else { 10 + i % 3 }))
+            .collect();
+        let d = Distribution::from_times(&times).unwrap();
+        assert_eq!("heavy-tailed", d.classify());
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use chrono::Utc;
+use handlebars::{handlebars_helper, no_escape, Handlebars};
+use rand::{distributions::Alphanumeric, Rng};
+use serde_json::json;
+
+const TEMPLATE_NAME: &str = "body";
+
+handlebars_helper!(random_int: |min: i64, max: i64| {
+    if min < max { rand::thread_rng().gen_range(min..=max) } else { min }
+});
+
+handlebars_helper!(random_string: |len: u64| {
+    rand::thread_rng()
+        .sample_iter(Alphanumeric)
+        .take(len as usize)
+        .map(char::from)
+        .collect::<String>()
+});
+
+handlebars_helper!(uuid: |*_args| {
+    let b: [u8; 16] = rand::thread_rng().gen();
+    let v = u128::from_be_bytes(b) & !(0xf000 << 64) & !(0xc << 60) | (0x4000 << 64) | (0x8 << 60);
+    let h = format!("{v:032x}");
+    format!("{}-{}-{}-{}-{}", &h[..8], &h[8..12], &h[12..16], &h[16..20], &h[20..])
+});
+
+/// A request body rendered per request with the Handlebars engine.
+///
+/// The following variables are available in the template:
+/// - `n`: the dispatch index of the request
+/// - `random`: a random unsigned 64 bit integer
+/// - `timestamp`: the current time formatted as RFC 3339
+/// - `timestamp_ms`: the current time as Unix timestamp in milliseconds
+///
+/// Additionally, the helpers `(random_int min max)`, `(random_string len)`
+/// and `(uuid)` can be used to generate realistic payloads.
+#[derive(Debug, Clone)]
+pub struct BodyTemplate {
+    registry: Handlebars<'static>,
+}
+
+impl BodyTemplate {
+    /// Compiles the template and renders it once, so that unknown
+    /// variables and helpers are reported before the run starts.
+    pub fn new(source: &str) -> Result<Self> {
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        registry.register_escape_fn(no_escape);
+        registry.register_helper("random_int", Box::new(random_int));
+        registry.register_helper("random_string", Box::new(random_string));
+        registry.register_helper("uuid", Box::new(uuid));
+        registry.register_template_string(TEMPLATE_NAME, source)?;
+
+        let template = Self { registry };
+        template.try_render(0)?;
+
+        Ok(template)
+    }
+
+    /// Returns the rendered template for the request with the dispatch
+    /// index `i`.
+    pub fn render(&self, i: usize) -> String {
+        // The variables always have the same types, so a template which
+        // rendered on creation can not fail to render later on.
+        self.try_render(i)
+            .expect("the template was rendered on creation")
+    }
+
+    fn try_render(&self, i: usize) -> Result<String> {
+        let now = Utc::now();
+        let data = json!({
+            "n": i,
+            "random": rand::random::<u64>(),
+            "timestamp": now.to_rfc3339(),
+            "timestamp_ms": now.timestamp_millis(),
+        });
+
+        Ok(self.registry.render(TEMPLATE_NAME, &data)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_variables() {
+        let t =
+            BodyTemplate::new(r#"{"id":{{n}},"s":"{{random_string 4}}","u":"{{uuid}}"}"#).unwrap();
+        let v: serde_json::Value = serde_json::from_str(&t.render(7)).unwrap();
+
+        assert_eq!(7, v["id"]);
+        assert_eq!(4, v["s"].as_str().unwrap().len());
+        let u = v["u"].as_str().unwrap();
+        assert_eq!(36, u.len());
+        assert_eq!(Some('4'), u.chars().nth(14));
+
+        let t = BodyTemplate::new("{{random_int 3 3}}").unwrap();
+        assert_eq!("3", t.render(0));
+    }
+
+    #[test]
+    fn unknown_variable() {
+        assert!(BodyTemplate::new("{{nope}}").is_err());
+    }
+}
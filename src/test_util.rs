@@ -0,0 +1,50 @@
+//! Helpers shared by the tests of the modules.
+
+use crate::request::Response;
+use chrono::Utc;
+use reqwest::{Method, StatusCode, Version};
+use std::{
+    fs,
+    path::PathBuf,
+    process,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Returns a successful response to a GET request which took `ms`
+/// milliseconds, without any of the optional measurements.
+pub fn response(ms: u64) -> Response {
+    Response {
+        index: 0,
+        method: Method::GET,
+        status: StatusCode::OK,
+        took: Duration::from_millis(ms),
+        timestamp: Utc::now(),
+        golden_match: None,
+        dns: None,
+        injected: false,
+        captured: vec![],
+        server_time: None,
+        body_size: None,
+        truncated: false,
+        sent_size: None,
+        content_encoding: None,
+        ttfb: None,
+        ttlb: None,
+        version: Version::HTTP_11,
+        conditional: false,
+        redirects: vec![],
+    }
+}
+
+/// Creates an empty directory in the temp dir which is unique to the
+/// calling test, so that parallel tests and concurrent runs of the test
+/// suite never share files.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!("rush-{name}-{}-{nanos}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
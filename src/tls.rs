@@ -0,0 +1,357 @@
+use crate::request::TlsVersion;
+use anyhow::Result;
+use rustls::{
+    client::{
+        ClientSessionMemoryCache, ClientSessionStore, HandshakeSignatureValid, ServerCertVerified,
+        ServerCertVerifier, Tls12ClientSessionValue, Tls12Resumption, Tls13ClientSessionValue,
+        WebPkiVerifier,
+    },
+    Certificate, ClientConfig, DigitallySignedStruct, NamedGroup, OwnedTrustAnchor, RootCertStore,
+    ServerName, SignatureScheme,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    ops::{Add, Sub},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
+
+/// The amount of server names whose sessions are cached, like the
+/// default of rustls.
+const CACHED_SESSIONS: usize = 256;
+
+/// The TLS settings of the connections, which match the ones of the
+/// reqwest client.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TlsOptions {
+    pub accept_invalid_certs: bool,
+    pub min_version: Option<TlsVersion>,
+    pub max_version: Option<TlsVersion>,
+}
+
+/// How the TLS handshakes of the connections are performed. Each request
+/// is sent over a new connection, so that each request performs a
+/// handshake of the given kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resumption {
+    /// Sessions are not cached, so that each handshake is a full one.
+    Off,
+    /// Sessions are cached, so that each handshake resumes the session of
+    /// a previous connection, if the server supports it.
+    Force,
+}
+
+impl FromStr for Resumption {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "force" => Ok(Self::Force),
+            _ => anyhow::bail!("unsupported TLS resumption: '{s}'; expected 'off' or 'force'"),
+        }
+    }
+}
+
+impl fmt::Display for Resumption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Off => "off",
+            Self::Force => "force",
+        })
+    }
+}
+
+/// The amount of TLS handshakes performed by a client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handshakes {
+    pub total: usize,
+    /// The amount of handshakes which resumed a previous session, i.e.
+    /// in which the server did not present its certificate.
+    pub resumed: usize,
+}
+
+impl Add for Handshakes {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            total: self.total + other.total,
+            resumed: self.resumed + other.resumed,
+        }
+    }
+}
+
+impl Sub for Handshakes {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            total: self.total - other.total,
+            resumed: self.resumed - other.resumed,
+        }
+    }
+}
+
+/// Counts the started handshakes and the verified server certificates,
+/// which are only presented in full handshakes. Handshakes which fail
+/// after the certificate has been verified still count as full ones.
+#[derive(Debug, Default)]
+pub struct HandshakeCounter {
+    started: AtomicUsize,
+    full: AtomicUsize,
+}
+
+impl HandshakeCounter {
+    pub fn get(&self) -> Handshakes {
+        let full = self.full.load(Ordering::Relaxed);
+        let total = self.started.load(Ordering::Relaxed).max(full);
+        Handshakes {
+            total,
+            resumed: total - full,
+        }
+    }
+}
+
+/// Builds the TLS configuration of connections made via hyper directly.
+pub fn client_config(opts: &TlsOptions) -> Result<ClientConfig> {
+    let versions: Vec<_> = [
+        (TlsVersion::Tls12, &rustls::version::TLS12),
+        (TlsVersion::Tls13, &rustls::version::TLS13),
+    ]
+    .into_iter()
+    .filter(|(v, _)| opts.min_version.is_none_or(|min| *v >= min))
+    .filter(|(v, _)| opts.max_version.is_none_or(|max| *v <= max))
+    .map(|(_, v)| v)
+    .collect();
+
+    let mut config = ClientConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(&versions)?
+        .with_root_certificates(roots())
+        .with_no_client_auth();
+    if opts.accept_invalid_certs {
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(AcceptAnyCert));
+    }
+    Ok(config)
+}
+
+/// Builds the TLS configuration of the reqwest client, which performs
+/// handshakes of the given kind and counts them.
+pub fn counting_config(
+    opts: &TlsOptions,
+    resumption: Resumption,
+    counter: Arc<HandshakeCounter>,
+) -> Result<ClientConfig> {
+    let mut config = client_config(opts)?;
+
+    let verifier: Arc<dyn ServerCertVerifier> = if opts.accept_invalid_certs {
+        Arc::new(AcceptAnyCert)
+    } else {
+        Arc::new(WebPkiVerifier::new(roots(), None))
+    };
+    config
+        .dangerous()
+        .set_certificate_verifier(Arc::new(CountingVerifier {
+            inner: verifier,
+            counter: counter.clone(),
+        }));
+
+    let store = CountingStore {
+        cache: (resumption == Resumption::Force)
+            .then(|| ClientSessionMemoryCache::new(CACHED_SESSIONS)),
+        counter,
+    };
+    config.resumption = rustls::client::Resumption::store(Arc::new(store));
+    if resumption == Resumption::Off {
+        config.resumption = config
+            .resumption
+            .tls12_resumption(Tls12Resumption::Disabled);
+    }
+
+    // reqwest ignores its own ALPN settings for a preconfigured TLS
+    // configuration, so HTTP/2 has to be offered here.
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+fn roots() -> RootCertStore {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            ta.subject,
+            ta.spki,
+            ta.name_constraints,
+        )
+    }));
+    roots
+}
+
+/// Accepts any server certificate, like reqwest does if invalid
+/// certificates are accepted.
+struct AcceptAnyCert;
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+/// Counts the verified server certificates and otherwise defers to the
+/// inner verifier.
+struct CountingVerifier {
+    inner: Arc<dyn ServerCertVerifier>,
+    counter: Arc<HandshakeCounter>,
+}
+
+impl ServerCertVerifier for CountingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.counter.full.fetch_add(1, Ordering::Relaxed);
+        self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+
+    fn request_scts(&self) -> bool {
+        self.inner.request_scts()
+    }
+}
+
+/// Caches the sessions, if any, and counts the started handshakes, each
+/// of which looks up a TLS 1.3 ticket exactly once.
+struct CountingStore {
+    cache: Option<ClientSessionMemoryCache>,
+    counter: Arc<HandshakeCounter>,
+}
+
+impl ClientSessionStore for CountingStore {
+    fn set_kx_hint(&self, server_name: &ServerName, group: NamedGroup) {
+        if let Some(cache) = &self.cache {
+            cache.set_kx_hint(server_name, group);
+        }
+    }
+
+    fn kx_hint(&self, server_name: &ServerName) -> Option<NamedGroup> {
+        self.cache.as_ref()?.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: &ServerName, value: Tls12ClientSessionValue) {
+        if let Some(cache) = &self.cache {
+            cache.set_tls12_session(server_name, value);
+        }
+    }
+
+    fn tls12_session(&self, server_name: &ServerName) -> Option<Tls12ClientSessionValue> {
+        self.cache.as_ref()?.tls12_session(server_name)
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName) {
+        if let Some(cache) = &self.cache {
+            cache.remove_tls12_session(server_name);
+        }
+    }
+
+    fn insert_tls13_ticket(&self, server_name: &ServerName, value: Tls13ClientSessionValue) {
+        if let Some(cache) = &self.cache {
+            cache.insert_tls13_ticket(server_name, value);
+        }
+    }
+
+    fn take_tls13_ticket(&self, server_name: &ServerName) -> Option<Tls13ClientSessionValue> {
+        self.counter.started.fetch_add(1, Ordering::Relaxed);
+        self.cache.as_ref()?.take_tls13_ticket(server_name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resumption() {
+        assert_eq!(Resumption::Off, "off".parse().unwrap());
+        assert_eq!(Resumption::Force, "force".parse().unwrap());
+        assert!("on".parse::<Resumption>().is_err());
+
+        let counter = Arc::new(HandshakeCounter::default());
+        let store = CountingStore {
+            cache: None,
+            counter: counter.clone(),
+        };
+        let name = ServerName::try_from("example.com").unwrap();
+        assert!(store.take_tls13_ticket(&name).is_none());
+        assert!(store.take_tls13_ticket(&name).is_none());
+        counter.full.fetch_add(1, Ordering::Relaxed);
+
+        let handshakes = counter.get();
+        assert_eq!(
+            Handshakes {
+                total: 2,
+                resumed: 1
+            },
+            handshakes
+        );
+        assert_eq!(
+            Handshakes {
+                total: 1,
+                resumed: 1
+            },
+            handshakes
+                - Handshakes {
+                    total: 1,
+                    resumed: 0
+                }
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use reqwest::header::HeaderValue;
+use std::{
+    process::Command,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// A bearer token obtained by executing a shell command, which can be
+/// refreshed while requests are being sent.
+#[derive(Debug)]
+pub struct TokenSource {
+    command: String,
+    state: Mutex<State>,
+    refreshes: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct State {
+    value: HeaderValue,
+    /// Incremented on each refresh, so that concurrent requests which
+    /// were rejected with the same token only trigger one refresh.
+    generation: u64,
+}
+
+impl TokenSource {
+    /// Executes the command to obtain the initial token.
+    pub fn new(command: String) -> Result<Self> {
+        let value = fetch(&command)?;
+        Ok(Self {
+            command,
+            state: Mutex::new(State {
+                value,
+                generation: 0,
+            }),
+            refreshes: AtomicUsize::new(0),
+        })
+    }
+
+    /// Returns the value of the Authorization header and the generation
+    /// of the token.
+    pub fn current(&self) -> (HeaderValue, u64) {
+        let state = self.state.lock().expect("token lock poisoned");
+        (state.value.clone(), state.generation)
+    }
+
+    /// Re-executes the command unless the token has already been
+    /// refreshed since the given generation was obtained. The lock is
+    /// held while the command runs, so that other requests wait for the
+    /// fresh token instead of executing the command as well.
+    pub fn refresh(&self, generation: u64) -> Result<()> {
+        let mut state = self.state.lock().expect("token lock poisoned");
+        if state.generation != generation {
+            return Ok(());
+        }
+
+        state.value = fetch(&self.command)?;
+        state.generation += 1;
+        self.refreshes.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Returns the amount of refreshes after the initial token.
+    pub fn refreshes(&self) -> usize {
+        self.refreshes.load(Ordering::Relaxed)
+    }
+}
+
+fn fetch(command: &str) -> Result<HeaderValue> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output()
+    } else {
+        Command::new("sh").args(["-c", command]).output()
+    }?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.trim() {
+            "" => anyhow::bail!("the token command failed with {}", output.status),
+            stderr => anyhow::bail!("the token command failed with {}: {stderr}", output.status),
+        }
+    }
+
+    let token = String::from_utf8(output.stdout)?;
+    let token = token.trim();
+    if token.is_empty() {
+        anyhow::bail!("the token command did not print a token");
+    }
+
+    let mut value: HeaderValue = format!("Bearer {token}").parse()?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn refresh_once_per_generation() {
+        let source = TokenSource::new("echo abc".into()).unwrap();
+        let (value, generation) = source.current();
+        assert_eq!("Bearer abc", value);
+
+        source.refresh(generation).unwrap();
+        source.refresh(generation).unwrap();
+        assert_eq!(1, source.refreshes());
+        assert_eq!(1, source.current().1);
+
+        assert!(TokenSource::new("exit 1".into()).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    duration::format_duration,
+    live::{Live, Snapshot},
+};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    widgets::{Block, Gauge, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+use std::{
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+/// The interval in which the dashboard is redrawn.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The exit code used when the run is aborted via Ctrl-C, following
+/// the shell convention of 128 + SIGINT.
+const ABORTED_EXIT_CODE: i32 = 130;
+
+/// Restores the terminal when dropped, so that it is also restored when
+/// the dashboard returns early due to an error.
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+/// Renders a live dashboard of the given aggregator until `done` is set.
+/// `total` is the amount of requests expected to be sent or 0, if it is
+/// not known upfront.
+///
+/// Since raw mode swallows the interrupt signal, Ctrl-C and 'q' are read
+/// as key events; the terminal is restored and the process exits.
+pub fn run(live: &Live, total: u64, done: &AtomicBool) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let guard = TerminalGuard;
+
+    while !done.load(Ordering::Relaxed) {
+        draw(&mut terminal, &live.snapshot(), total)?;
+
+        if event::poll(REFRESH_INTERVAL)? {
+            if let Event::Key(key) = event::read()? {
+                let ctrl_c =
+                    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.kind == KeyEventKind::Press && (ctrl_c || key.code == KeyCode::Char('q')) {
+                    // The terminal shows the cursor again when dropped.
+                    drop(terminal);
+                    drop(guard);
+                    std::process::exit(ABORTED_EXIT_CODE);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn draw(terminal: &mut DefaultTerminal, s: &Snapshot, total: u64) -> io::Result<()> {
+    terminal.draw(|f| render(f, s, total))?;
+    Ok(())
+}
+
+fn render(f: &mut Frame, s: &Snapshot, total: u64) {
+    let [progress, overview, statuses, help] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(7),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(f.area());
+
+    let ratio = if total == 0 {
+        0f64
+    } else {
+        (s.completed as f64 / total as f64).min(1f64)
+    };
+    f.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title(" Progress "))
+            .ratio(ratio)
+            .label(if total == 0 {
+                s.completed.to_string()
+            } else {
+                format!("{} / {total}", s.completed)
+            }),
+        progress,
+    );
+
+    let lines = [
+        format!("Elapsed:    {:>10.2?}", s.elapsed),
+        format!("Req/s:      {:>10.2}", s.current_rps),
+        format!("Median:     {:>10.4}", format_duration(s.p50)),
+        format!("90th %ile.: {:>10.4}", format_duration(s.p90)),
+        format!("99th %ile.: {:>10.4}", format_duration(s.p99)),
+    ];
+    f.render_widget(
+        Paragraph::new(lines.join("\n")).block(Block::bordered().title(" Latency ")),
+        overview,
+    );
+
+    let rows = s.statuses.iter().map(|(status, &n)| {
+        let prct = n as f64 / s.completed.max(1) as f64 * 100f64;
+        Row::new([status.clone(), n.to_string(), format!("{prct:.2}%")])
+    });
+    f.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Min(20),
+                Constraint::Length(12),
+                Constraint::Length(10),
+            ],
+        )
+        .block(Block::bordered().title(" Statuses ")),
+        statuses,
+    );
+
+    f.render_widget(Paragraph::new("Press q or Ctrl-C to abort"), help);
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    fs,
+    hash::{Hash, Hasher},
+    num::NonZeroUsize,
+    path::Path,
+};
+
+/// Reads one URL per line from the given file; blank lines and lines
+/// starting with '#' are skipped.
+pub fn read_urls(path: impl AsRef<Path>) -> Result<Vec<String>> {
+    let urls: Vec<_> = fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect();
+
+    if urls.is_empty() {
+        anyhow::bail!("the URL file does not contain any URLs");
+    }
+
+    Ok(urls)
+}
+
+/// Removes duplicate URLs while preserving the order in which they have
+/// been seen first. Returns the amount of removed duplicates.
+pub fn dedup(urls: &mut Vec<String>) -> usize {
+    let mut seen = HashSet::new();
+    let len = urls.len();
+    urls.retain(|u| seen.insert(u.clone()));
+    len - urls.len()
+}
+
+/// Routes the requests of simulated users to the URLs, so that all
+/// requests of a user are sent to the same URL. The request with the
+/// dispatch index `i` belongs to the user `i % users`, which is routed via
+/// rendezvous hashing: each user picks the URL with the highest hash of
+/// the user and the URL, so that adding or removing a URL only moves the
+/// users of that URL.
+#[derive(Debug)]
+pub struct Sticky {
+    /// The index of the URL of each user.
+    routes: Vec<usize>,
+}
+
+/// The users and requests routed to a URL by [`Sticky`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StickyTarget {
+    pub url: String,
+    pub users: usize,
+    pub requests: usize,
+}
+
+impl Sticky {
+    pub fn new(users: NonZeroUsize, urls: &[Url]) -> Self {
+        let routes = (0..users.get())
+            .map(|user| {
+                let key = Self::key(user);
+                (0..urls.len())
+                    .max_by_key(|&j| {
+                        let mut hasher = DefaultHasher::new();
+                        (&key, urls[j].as_str()).hash(&mut hasher);
+                        hasher.finish()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        Self { routes }
+    }
+
+    /// Returns the key identifying the user, e.g. 'user-7'.
+    pub fn key(user: usize) -> String {
+        format!("user-{user}")
+    }
+
+    pub fn user(&self, i: usize) -> usize {
+        i % self.routes.len()
+    }
+
+    /// Returns the index of the URL of the request with the dispatch
+    /// index `i`.
+    pub fn route(&self, i: usize) -> usize {
+        self.routes[self.user(i)]
+    }
+
+    /// Returns the amount of users and of the requests with the given
+    /// dispatch indices routed to each URL.
+    pub fn distribution(
+        &self,
+        urls: &[Url],
+        indices: impl Iterator<Item = usize>,
+    ) -> Vec<StickyTarget> {
+        let mut targets: Vec<_> = urls
+            .iter()
+            .map(|u| StickyTarget {
+                url: u.to_string(),
+                users: 0,
+                requests: 0,
+            })
+            .collect();
+        for &j in &self.routes {
+            targets[j].users += 1;
+        }
+        for i in indices {
+            targets[self.route(i)].requests += 1;
+        }
+        targets
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dedup_in_order() {
+        let mut urls: Vec<_> = ["b", "a", "b", "c", "a"].map(String::from).into();
+        assert_eq!(2, dedup(&mut urls));
+        assert_eq!(vec!["b", "a", "c"], urls);
+    }
+
+    #[test]
+    fn sticky_routes() {
+        let urls: Vec<Url> = ["http://a/", "http://b/", "http://c/"]
+            .map(|u| u.parse().unwrap())
+            .into();
+        let users = NonZeroUsize::new(300).unwrap();
+        let sticky = Sticky::new(users, &urls);
+
+        // Each user is routed to the same URL with every request.
+        assert_eq!(sticky.route(7), sticky.route(307));
+        assert_eq!(7, sticky.user(607));
+
+        let targets = sticky.distribution(&urls, 0..600);
+        assert_eq!(300, targets.iter().map(|t| t.users).sum::<usize>());
+        assert_eq!(600, targets.iter().map(|t| t.requests).sum::<usize>());
+        assert!(targets.iter().all(|t| t.users > 50));
+
+        // Removing a URL only moves the users routed to it.
+        let fewer = Sticky::new(users, &urls[..2]);
+        for user in 0..300 {
+            if sticky.route(user) < 2 {
+                assert_eq!(sticky.route(user), fewer.route(user));
+            }
+        }
+    }
+}